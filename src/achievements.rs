@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings},
+    game::{
+        GameMode, GameState,
+        attributes::AttributeKind,
+        bomb::{BombDefusedEvent, MatchBomb},
+        cell_line::{self, CellLine, LineCompletedEvent, shared_attributes},
+        game_grid::{GameGrid, PieceSound, PopCellEvent},
+        rules::GameRules,
+    },
+    locale::LocalizedText,
+    save,
+    screen::components::ScreenState,
+    ui_theme::{NodeRole, UiTheme},
+};
+
+const SAVE_NAME: &str = "achievements";
+const LONG_LINE_LENGTH: usize = 10;
+const BOMB_SQUAD_COUNT: u64 = 5;
+const BUBBLE_WRAP_COUNT: u64 = 500;
+const SCALE: [PieceSound; 5] = [
+    PieceSound::C,
+    PieceSound::D,
+    PieceSound::E,
+    PieceSound::F,
+    PieceSound::G,
+];
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Achievements>(SAVE_NAME))
+            .add_event::<AchievementUnlockedEvent>()
+            .add_systems(
+                Update,
+                (
+                    check_line_achievements
+                        .run_if(on_event::<LineCompletedEvent>)
+                        .before(cell_line::on_line_complete),
+                    check_defuse_achievements.run_if(on_event::<BombDefusedEvent>),
+                    count_pops.run_if(on_event::<PopCellEvent>),
+                )
+                    .run_if(in_state(GameMode::Standard)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (check_game_over_achievements, save_achievements).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    spawn_toasts.run_if(on_event::<AchievementUnlockedEvent>),
+                    despawn_toasts,
+                ),
+            )
+            .add_systems(OnExit(ScreenState::Game), save_achievements)
+            .add_systems(Last, save_achievements.run_if(on_event::<AppExit>));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AchievementId {
+    LongLine,
+    BombSquad,
+    PerfectChain,
+    Scale,
+    BubbleWrap,
+    Kaboom,
+}
+
+impl AchievementId {
+    pub const ALL: [AchievementId; 6] = [
+        AchievementId::LongLine,
+        AchievementId::BombSquad,
+        AchievementId::PerfectChain,
+        AchievementId::Scale,
+        AchievementId::BubbleWrap,
+        AchievementId::Kaboom,
+    ];
+
+    /// Start of this achievement's keys in the string tables
+    fn key(&self) -> &'static str {
+        match self {
+            AchievementId::LongLine => "achievement.long_line",
+            AchievementId::BombSquad => "achievement.bomb_squad",
+            AchievementId::PerfectChain => "achievement.perfect_chain",
+            AchievementId::Scale => "achievement.scale",
+            AchievementId::BubbleWrap => "achievement.bubble_wrap",
+            AchievementId::Kaboom => "achievement.kaboom",
+        }
+    }
+
+    pub fn name(&self) -> LocalizedText {
+        LocalizedText::new(format!("{}.name", self.key()))
+    }
+
+    pub fn description(&self) -> LocalizedText {
+        LocalizedText::new(format!("{}.description", self.key()))
+    }
+}
+
+/// Unlocked achievements and the lifetime counters some of them track
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Achievements {
+    unlocked: Vec<AchievementId>,
+    pieces_popped: u64,
+}
+
+impl Achievements {
+    pub fn is_unlocked(&self, id: AchievementId) -> bool {
+        self.unlocked.contains(&id)
+    }
+
+    /// Returns true the first time an achievement is unlocked
+    fn unlock(&mut self, id: AchievementId) -> bool {
+        if self.is_unlocked(id) {
+            return false;
+        }
+        self.unlocked.push(id);
+        true
+    }
+}
+
+#[derive(Event)]
+pub struct AchievementUnlockedEvent(pub AchievementId);
+
+#[derive(Component)]
+pub struct AchievementToast(Timer);
+
+impl Default for AchievementToast {
+    fn default() -> Self {
+        AchievementToast(Timer::new(Duration::from_secs_f32(3.0), TimerMode::Once))
+    }
+}
+
+//Systems
+
+fn unlock(
+    achievements: &mut ResMut<Achievements>,
+    ew_unlocked: &mut EventWriter<AchievementUnlockedEvent>,
+    id: AchievementId,
+) {
+    if achievements.unlock(id) {
+        info!("Achievement unlocked: {id:?}");
+        ew_unlocked.write(AchievementUnlockedEvent(id));
+    }
+}
+
+fn check_line_achievements(
+    cell_line: Res<CellLine>,
+    grid: Res<GameGrid>,
+    rules: Res<GameRules>,
+    mut achievements: ResMut<Achievements>,
+    mut ew_unlocked: EventWriter<AchievementUnlockedEvent>,
+) {
+    let cells = cell_line.cells();
+    if !rules.is_scoring(cells.len()) {
+        return;
+    }
+
+    if cells.len() >= LONG_LINE_LENGTH {
+        unlock(&mut achievements, &mut ew_unlocked, AchievementId::LongLine);
+    }
+
+    let pieces: Vec<_> = cells
+        .iter()
+        .filter_map(|cell| *grid.get_piece(*cell))
+        .collect();
+
+    let perfect = pieces
+        .windows(2)
+        .all(|step| shared_attributes(&step[0], &step[1]).len() == AttributeKind::COUNT);
+    if perfect {
+        unlock(
+            &mut achievements,
+            &mut ew_unlocked,
+            AchievementId::PerfectChain,
+        );
+    }
+
+    let sounds: Vec<PieceSound> = pieces.iter().map(|piece| piece.sound()).collect();
+    if sounds.windows(SCALE.len()).any(|notes| notes == SCALE) {
+        unlock(&mut achievements, &mut ew_unlocked, AchievementId::Scale);
+    }
+}
+
+fn check_defuse_achievements(
+    bomb: Res<MatchBomb>,
+    mut achievements: ResMut<Achievements>,
+    mut ew_unlocked: EventWriter<AchievementUnlockedEvent>,
+) {
+    if bomb.defused_count() >= BOMB_SQUAD_COUNT {
+        unlock(
+            &mut achievements,
+            &mut ew_unlocked,
+            AchievementId::BombSquad,
+        );
+    }
+}
+
+fn count_pops(
+    mut er_pop_cell: EventReader<PopCellEvent>,
+    mut achievements: ResMut<Achievements>,
+    mut ew_unlocked: EventWriter<AchievementUnlockedEvent>,
+) {
+    achievements.pieces_popped += er_pop_cell.read().count() as u64;
+    if achievements.pieces_popped >= BUBBLE_WRAP_COUNT {
+        unlock(
+            &mut achievements,
+            &mut ew_unlocked,
+            AchievementId::BubbleWrap,
+        );
+    }
+}
+
+fn check_game_over_achievements(
+    mut achievements: ResMut<Achievements>,
+    mut ew_unlocked: EventWriter<AchievementUnlockedEvent>,
+) {
+    unlock(&mut achievements, &mut ew_unlocked, AchievementId::Kaboom);
+}
+
+fn save_achievements(achievements: Res<Achievements>) {
+    save::save(SAVE_NAME, achievements.as_ref());
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut er_unlocked: EventReader<AchievementUnlockedEvent>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    toast_q: Query<(), With<AchievementToast>>,
+    theme: Res<UiTheme>,
+) {
+    //Stack new toasts under the ones already showing
+    let shown = toast_q.iter().count();
+    for (offset, event) in (shown..).zip(er_unlocked.read()) {
+        let toast = commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0 + 60.0 * offset as f32),
+                    right: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                BackgroundColor(theme.button_background_normal),
+                BorderColor(theme.button_background_hover),
+                NodeRole::Card { highlighted: true },
+                AchievementToast::default(),
+            ))
+            .id();
+        let title_text = commands
+            .spawn((
+                LocalizedText::new("achievement.unlocked"),
+                theme.text_style(),
+            ))
+            .id();
+        let name_text = commands.spawn((event.0.name(), theme.text_style())).id();
+        commands
+            .entity(toast)
+            .add_children(&[title_text, name_text]);
+
+        ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+            EffectName::UiConfirm,
+            Some(false),
+            None,
+        )));
+    }
+}
+
+fn despawn_toasts(
+    mut commands: Commands,
+    mut toast_q: Query<(Entity, &mut AchievementToast)>,
+    time: Res<Time>,
+) {
+    for (entity, mut toast) in toast_q.iter_mut() {
+        toast.0.tick(time.delta());
+        if toast.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{locale::LocalizedText, save};
+
+// Input actions and the keys and gamepad buttons bound to them.
+// Systems ask whether an action was pressed rather than checking keys,
+// so every binding can be changed from the controls tab and is kept in a settings file.
+
+const SAVE_NAME: &str = "controls";
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Bindings>(SAVE_NAME).with_defaults())
+            .add_systems(Update, save_bindings.run_if(resource_changed::<Bindings>));
+    }
+}
+
+/// Where an action is used. Actions can only share a binding if they are never used in the same place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionContext {
+    Menu,
+    Game,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CursorDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl CursorDirection {
+    pub const ALL: [CursorDirection; 8] = [
+        CursorDirection::Up,
+        CursorDirection::Down,
+        CursorDirection::Left,
+        CursorDirection::Right,
+        CursorDirection::UpLeft,
+        CursorDirection::UpRight,
+        CursorDirection::DownLeft,
+        CursorDirection::DownRight,
+    ];
+
+    /// The step on the board. Up on screen is +y
+    pub fn step(&self) -> IVec2 {
+        match self {
+            CursorDirection::Up => IVec2::new(0, 1),
+            CursorDirection::Down => IVec2::new(0, -1),
+            CursorDirection::Left => IVec2::new(-1, 0),
+            CursorDirection::Right => IVec2::new(1, 0),
+            CursorDirection::UpLeft => IVec2::new(-1, 1),
+            CursorDirection::UpRight => IVec2::new(1, 1),
+            CursorDirection::DownLeft => IVec2::new(-1, -1),
+            CursorDirection::DownRight => IVec2::new(1, -1),
+        }
+    }
+
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            CursorDirection::Up => "direction.up",
+            CursorDirection::Down => "direction.down",
+            CursorDirection::Left => "direction.left",
+            CursorDirection::Right => "direction.right",
+            CursorDirection::UpLeft => "direction.up_left",
+            CursorDirection::UpRight => "direction.up_right",
+            CursorDirection::DownLeft => "direction.down_left",
+            CursorDirection::DownRight => "direction.down_right",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// Leaves a screen or backs out of a menu
+    Back,
+    /// Drops the line in progress, or pauses when there is none
+    Cancel,
+    StepBack,
+    Confirm,
+    Pause,
+    Hint,
+    MoveCursor(CursorDirection),
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::Back,
+        Action::Cancel,
+        Action::StepBack,
+        Action::Confirm,
+        Action::Pause,
+        Action::Hint,
+        Action::MoveCursor(CursorDirection::Up),
+        Action::MoveCursor(CursorDirection::Down),
+        Action::MoveCursor(CursorDirection::Left),
+        Action::MoveCursor(CursorDirection::Right),
+        Action::MoveCursor(CursorDirection::UpLeft),
+        Action::MoveCursor(CursorDirection::UpRight),
+        Action::MoveCursor(CursorDirection::DownLeft),
+        Action::MoveCursor(CursorDirection::DownRight),
+    ];
+
+    pub fn label(&self) -> LocalizedText {
+        match self {
+            Action::Back => LocalizedText::new("action.back"),
+            Action::Cancel => LocalizedText::new("action.cancel"),
+            Action::StepBack => LocalizedText::new("action.step_back"),
+            Action::Confirm => LocalizedText::new("action.confirm"),
+            Action::Pause => LocalizedText::new("action.pause"),
+            Action::Hint => LocalizedText::new("action.hint"),
+            Action::MoveCursor(direction) => {
+                LocalizedText::new("action.move").arg_key("direction", direction.label_key())
+            }
+        }
+    }
+
+    pub fn contexts(&self) -> &'static [ActionContext] {
+        match self {
+            Action::Back => &[ActionContext::Menu],
+            //Menus are navigated and pressed with these as well
+            Action::Pause | Action::Confirm | Action::MoveCursor(_) => {
+                &[ActionContext::Game, ActionContext::Menu]
+            }
+            _ => &[ActionContext::Game],
+        }
+    }
+
+    fn default_bindings(&self) -> Vec<Binding> {
+        use Binding::{Gamepad, Key};
+        match self {
+            Action::Back => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::East)],
+            Action::Cancel => vec![Key(KeyCode::Escape)],
+            Action::StepBack => vec![Key(KeyCode::Backspace), Gamepad(GamepadButton::East)],
+            Action::Confirm => vec![
+                Key(KeyCode::Space),
+                Key(KeyCode::Enter),
+                Key(KeyCode::NumpadEnter),
+                Gamepad(GamepadButton::South),
+            ],
+            Action::Pause => vec![Gamepad(GamepadButton::Start)],
+            Action::Hint => vec![Key(KeyCode::KeyH), Gamepad(GamepadButton::North)],
+            Action::MoveCursor(direction) => match direction {
+                CursorDirection::Up => vec![
+                    Key(KeyCode::ArrowUp),
+                    Key(KeyCode::KeyW),
+                    Key(KeyCode::Numpad8),
+                    Gamepad(GamepadButton::DPadUp),
+                ],
+                CursorDirection::Down => vec![
+                    Key(KeyCode::ArrowDown),
+                    Key(KeyCode::KeyS),
+                    Key(KeyCode::Numpad2),
+                    Gamepad(GamepadButton::DPadDown),
+                ],
+                CursorDirection::Left => vec![
+                    Key(KeyCode::ArrowLeft),
+                    Key(KeyCode::KeyA),
+                    Key(KeyCode::Numpad4),
+                    Gamepad(GamepadButton::DPadLeft),
+                ],
+                CursorDirection::Right => vec![
+                    Key(KeyCode::ArrowRight),
+                    Key(KeyCode::KeyD),
+                    Key(KeyCode::Numpad6),
+                    Gamepad(GamepadButton::DPadRight),
+                ],
+                CursorDirection::UpLeft => vec![Key(KeyCode::KeyQ), Key(KeyCode::Numpad7)],
+                CursorDirection::UpRight => vec![Key(KeyCode::KeyE), Key(KeyCode::Numpad9)],
+                CursorDirection::DownLeft => vec![Key(KeyCode::KeyZ), Key(KeyCode::Numpad1)],
+                CursorDirection::DownRight => vec![Key(KeyCode::KeyC), Key(KeyCode::Numpad3)],
+            },
+        }
+    }
+}
+
+/// A key or a gamepad button. Gamepad buttons match on any connected gamepad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                name.strip_prefix("Key").unwrap_or(&name).to_string()
+            }
+            Binding::Gamepad(button) => match button {
+                GamepadButton::South => "Pad A".to_string(),
+                GamepadButton::East => "Pad B".to_string(),
+                GamepadButton::West => "Pad X".to_string(),
+                GamepadButton::North => "Pad Y".to_string(),
+                other => format!("Pad {other:?}"),
+            },
+        }
+    }
+}
+
+/// Every action and what it is bound to
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bindings(pub BTreeMap<Action, Vec<Binding>>);
+
+impl Bindings {
+    pub fn defaults() -> Self {
+        Bindings(
+            Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        )
+    }
+
+    /// Fills in any action missing from a settings file written before it existed
+    pub fn with_defaults(mut self) -> Self {
+        for action in Action::ALL {
+            self.0
+                .entry(action)
+                .or_insert_with(|| action.default_bindings());
+        }
+        self
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn get_mut(&mut self, action: Action) -> &mut Vec<Binding> {
+        self.0.entry(action).or_default()
+    }
+
+    /// Bindings shared by two actions that can be used in the same place
+    pub fn conflicts(&self) -> Vec<(Binding, Action, Action)> {
+        let mut conflicts = vec![];
+        for (i, first) in Action::ALL.iter().enumerate() {
+            for second in Action::ALL.iter().skip(i + 1) {
+                if !first
+                    .contexts()
+                    .iter()
+                    .any(|context| second.contexts().contains(context))
+                {
+                    continue;
+                }
+                for binding in self.get(*first) {
+                    if self.get(*second).contains(binding) {
+                        conflicts.push((*binding, *first, *second));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Reads actions from the keyboard and every connected gamepad
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+    bindings: Res<'w, Bindings>,
+}
+
+impl ActionInput<'_, '_> {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.any(action, ButtonInput::just_pressed, Gamepad::just_pressed)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.any(action, ButtonInput::just_released, Gamepad::just_released)
+    }
+
+    fn any(
+        &self,
+        action: Action,
+        key_check: fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+        button_check: fn(&Gamepad, GamepadButton) -> bool,
+    ) -> bool {
+        self.bindings
+            .get(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => key_check(&self.keys, *key),
+                Binding::Gamepad(button) => self
+                    .gamepads
+                    .iter()
+                    .any(|gamepad| button_check(gamepad, *button)),
+            })
+    }
+
+    /// The cursor step from every direction pressed this frame
+    pub fn cursor_step(&self) -> IVec2 {
+        CursorDirection::ALL
+            .iter()
+            .filter(|direction| self.just_pressed(Action::MoveCursor(**direction)))
+            .map(|direction| direction.step())
+            .sum::<IVec2>()
+            .clamp(IVec2::NEG_ONE, IVec2::ONE)
+    }
+}
+
+//Systems
+
+fn save_bindings(bindings: Res<Bindings>) {
+    save::save(SAVE_NAME, &*bindings);
+}
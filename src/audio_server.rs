@@ -1,63 +1,46 @@
-use bevy::{audio::Volume, prelude::*};
+use bevy::prelude::*;
 
+use adaptive::AdaptiveMusicPlugin;
 use components::*;
+use music::MusicDirectorPlugin;
+use synth::SynthPlugin;
 
-use crate::screen::components::ScreenState;
+use crate::{game::pause::PauseState, save, screen::components::ScreenState};
 
+pub mod adaptive;
 pub mod components;
+pub mod music;
+pub mod synth;
+
+const SAVE_NAME: &str = "audio";
+
 pub struct AudioServerPlugin;
 
 impl Plugin for AudioServerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(AudioLibrary::default())
             .add_event::<PlaySoundEffectEvent>()
-            .add_event::<MusicVolumeChangedEvent>()
-            .add_event::<MasterVolumeChangedEvent>()
+            .add_plugins((MusicDirectorPlugin, AdaptiveMusicPlugin, SynthPlugin))
             .add_systems(Startup, setup_audio_server)
-            .add_systems(OnExit(ScreenState::Splash), play_music)
-            .insert_resource(AudioLibrary::default())
-            .insert_resource(AudioSettings::default())
+            .insert_resource(save::load::<AudioSettings>(SAVE_NAME))
             .add_systems(Update, (play_sfx, clear_sfx))
             .add_systems(
                 Update,
-                (music_volume, master_volume).after(setup_audio_server),
-            );
+                apply_audio_settings.run_if(resource_changed::<AudioSettings>),
+            )
+            //Sliders change the settings every frame they are dragged,
+            //so they are saved on the way out of the options instead
+            .add_systems(OnExit(ScreenState::Option), save_audio_settings)
+            .add_systems(OnExit(PauseState::Paused), save_audio_settings)
+            .add_systems(OnExit(ScreenState::Game), save_audio_settings)
+            .add_systems(Last, save_audio_settings.run_if(on_event::<AppExit>));
     }
 }
 
-pub fn setup_audio_server(mut audio_settings: ResMut<AudioSettings>) {
-    audio_settings.sfx_vol = 0.3;
-    audio_settings.master_vol = 0.3;
-    audio_settings.music_vol = 0.3;
-
+pub fn setup_audio_server() {
     info!("Audio Server Plugin initalized");
 }
 
-fn play_music(
-    audio_library: ResMut<AudioLibrary>,
-    mut commands: Commands,
-    audio_settings: Res<AudioSettings>,
-    asset_server: Res<AssetServer>,
-) {
-    commands.spawn((
-        AudioPlayer::new(
-            asset_server.load(
-                audio_library
-                    .music
-                    .get(&MusicTrackName::TrackOne)
-                    .unwrap()
-                    .clone(),
-            ),
-        ),
-        MusicTrack,
-        PlaybackSettings {
-            mode: bevy::audio::PlaybackMode::Despawn,
-            volume: Volume::Linear(audio_settings.music_vol),
-            ..default()
-        },
-    ));
-}
-
 pub fn play_sfx(
     mut commands: Commands,
     mut event_reader: EventReader<PlaySoundEffectEvent>,
@@ -75,7 +58,7 @@ pub fn play_sfx(
         };
 
         let playback_settings = PlaybackSettings {
-            volume: bevy::audio::Volume::Linear(asettings.sfx_vol),
+            volume: asettings.gain(sfx_settings.get_name().bus()),
             speed: pitch,
             ..default()
         };
@@ -83,51 +66,30 @@ pub fn play_sfx(
         let path = *audio_library.sfx.get(&event.0.get_name()).unwrap();
         let handle = asset_server.load(path);
         let audio_player = AudioPlayer::new(handle);
-        commands.spawn((audio_player, playback_settings, SoundEffect));
+        commands.spawn((
+            audio_player,
+            playback_settings,
+            SoundEffect,
+            sfx_settings.get_name().bus(),
+        ));
     }
 }
 
-pub fn music_volume(
-    mut event_reader: EventReader<MusicVolumeChangedEvent>,
-    mut sink_q: Query<&mut AudioSink, With<MusicTrack>>,
+///Master goes into GlobalVolume, which Bevy multiplies into each sound as it starts.
+///Sounds already playing have their volume set outright, master included, so nothing is applied twice
+pub fn apply_audio_settings(
+    settings: Res<AudioSettings>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut sink_q: Query<(&mut AudioSink, &AudioBus)>,
 ) {
-    if let Ok(mut sink) = sink_q.single_mut() {
-        for event in event_reader.read() {
-            if !event.0 {
-                let newvol = sink.volume() - Volume::Linear(0.1);
-                sink.set_volume(newvol);
-            } else {
-                let newvol = sink.volume() + Volume::Linear(0.1);
-                sink.set_volume(newvol);
-            }
-        }
+    global_volume.volume = settings.gain(AudioBus::Master);
+    for (mut sink, bus) in sink_q.iter_mut() {
+        sink.set_volume(settings.output(*bus));
     }
 }
 
-pub fn master_volume(
-    mut event_reader: EventReader<MasterVolumeChangedEvent>,
-    mut sinks_q: Query<&mut AudioSink>,
-    mut vol: ResMut<GlobalVolume>,
-) {
-    for event in event_reader.read() {
-        if !event.0 {
-            if vol.volume - Volume::Linear(0.1) >= Volume::Linear(0.0) {
-                let dec_val = vol.volume - Volume::Linear(0.1);
-                vol.volume = dec_val;
-            }
-            for mut sink in sinks_q.iter_mut() {
-                let newvol = sink.volume() - Volume::Linear(0.1);
-                sink.set_volume(newvol);
-            }
-        } else {
-            let inc_val = vol.volume + Volume::Linear(0.1);
-            vol.volume = inc_val;
-            for mut sink in sinks_q.iter_mut() {
-                let newvol = sink.volume() + Volume::Linear(0.1);
-                sink.set_volume(newvol);
-            }
-        }
-    }
+fn save_audio_settings(settings: Res<AudioSettings>) {
+    save::save(SAVE_NAME, &*settings);
 }
 
 pub fn clear_sfx(mut commands: Commands, sfx_q: Query<(Entity, &AudioSink), With<SoundEffect>>) {
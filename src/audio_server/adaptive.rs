@@ -0,0 +1,260 @@
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use super::{
+    components::*,
+    music::{FadeIn, FadeOut, MusicDirector, TrackGain, change_music_scene, fade_music},
+    synth::SynthLoop,
+};
+use crate::game::{
+    GameState,
+    bomb::{BombDefusedEvent, MatchBomb},
+};
+
+/// Turns left when the tension stem starts to come in
+const TENSE_TURNS: f32 = 5.0;
+/// Points needed per turn that still count as comfortable. The percussion is at full at this pace
+const COMFORTABLE_POINTS_PER_TURN: f32 = 100.0;
+/// How quickly stems move towards their new levels, per second
+const STEM_FOLLOW_RATE: f32 = 1.5;
+
+// Music for a standard run, built from stems that loop together.
+// Their levels follow the bomb, so the music tightens as the countdown runs out.
+// Stems wait paused until every one of them has loaded, then start on the same frame.
+// The base plays through once, and when it ends every stem is started over with it,
+// so the shorter stems line up with the base again on each time round.
+pub struct AdaptiveMusicPlugin;
+
+impl Plugin for AdaptiveMusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, prepare_stems)
+            .add_systems(
+                Update,
+                (start_stems, restart_stems, sync_stems, mix_stems)
+                    .chain()
+                    .after(change_music_scene)
+                    .before(fade_music),
+            )
+            .add_systems(
+                Update,
+                play_defuse_stinger.run_if(on_event::<BombDefusedEvent>),
+            )
+            .add_systems(OnEnter(GameState::GameOver), silence_music);
+    }
+}
+
+/// A stem that has loaded or is loading, and waits for the others before playing
+#[derive(Component)]
+pub struct StemWaiting {
+    /// Whether it comes in from silence. Stems restarted with the base carry on at their level
+    fade_in: bool,
+}
+
+/// The audio of every stem, loaded or rendered once at startup
+#[derive(Resource, Default)]
+struct StemAudio(HashMap<MusicStem, StemHandle>);
+
+enum StemHandle {
+    File(Handle<AudioSource>),
+    Synth(Handle<SynthLoop>),
+}
+
+//Marker for a one shot played over the music
+#[derive(Component)]
+pub struct Stinger;
+
+/// The level a stem should sit at for the bomb's current state
+fn stem_target(stem: MusicStem, bomb: &MatchBomb) -> f32 {
+    match stem {
+        MusicStem::Base => 1.0,
+        MusicStem::Percussion => {
+            let pace = bomb.points_remaining() as f32 / bomb.turns_remaining().max(1) as f32;
+            (pace / COMFORTABLE_POINTS_PER_TURN).clamp(0.0, 1.0)
+        }
+        MusicStem::Tension => (1.0 - bomb.turns_remaining() as f32 / TENSE_TURNS).clamp(0.0, 1.0),
+    }
+}
+
+/// Spawns a stem paused, to wait for the others
+fn spawn_stem(
+    commands: &mut Commands,
+    stem: MusicStem,
+    audio: &StemHandle,
+    gain: f32,
+    fade_in: bool,
+) {
+    //The base ending is what restarts the stems, so it can't loop by itself
+    let mode = match stem {
+        MusicStem::Base => PlaybackMode::Once,
+        _ => PlaybackMode::Loop,
+    };
+    let mut entity = commands.spawn((
+        MusicTrack,
+        stem,
+        AudioBus::Music,
+        TrackGain(gain),
+        StemWaiting { fade_in },
+        PlaybackSettings {
+            mode,
+            volume: Volume::SILENT,
+            paused: true,
+            ..default()
+        },
+    ));
+    match audio {
+        StemHandle::File(handle) => entity.insert(AudioPlayer(handle.clone())),
+        StemHandle::Synth(handle) => entity.insert(AudioPlayer(handle.clone())),
+    };
+}
+
+//Systems
+
+fn prepare_stems(
+    mut commands: Commands,
+    audio_library: Res<AudioLibrary>,
+    asset_server: Res<AssetServer>,
+    mut loops: ResMut<Assets<SynthLoop>>,
+) {
+    let handles = audio_library
+        .stems
+        .iter()
+        .map(|(stem, source)| {
+            let handle = match source {
+                StemSource::File(path) => StemHandle::File(asset_server.load(*path)),
+                StemSource::Synth(pattern) => {
+                    StemHandle::Synth(loops.add(SynthLoop::render(pattern)))
+                }
+            };
+            (*stem, handle)
+        })
+        .collect();
+    commands.insert_resource(StemAudio(handles));
+}
+
+///Spawns every stem paused once a standard run starts
+fn start_stems(
+    mut commands: Commands,
+    director: Res<MusicDirector>,
+    stem_audio: Res<StemAudio>,
+    bomb: Res<MatchBomb>,
+    stem_q: Query<(), (With<MusicStem>, Without<FadeOut>)>,
+) {
+    if director.scene() != Some(MusicScene::Bomb) || !stem_q.is_empty() {
+        return;
+    }
+
+    for stem in MusicStem::ALL {
+        let Some(audio) = stem_audio.0.get(&stem) else {
+            warn!("No audio for music stem {stem:?}");
+            continue;
+        };
+        spawn_stem(&mut commands, stem, audio, stem_target(stem, &bomb), true);
+    }
+}
+
+///Once the base has played through, every stem is started over with it at the level it had
+fn restart_stems(
+    mut commands: Commands,
+    stem_audio: Res<StemAudio>,
+    stem_q: Query<
+        (Entity, &MusicStem, &TrackGain, Option<&AudioSink>),
+        (Without<StemWaiting>, Without<FadeOut>),
+    >,
+) {
+    let base_ended = stem_q.iter().any(|(_, stem, _, sink)| {
+        *stem == MusicStem::Base && sink.is_some_and(|sink| sink.empty())
+    });
+    if !base_ended {
+        return;
+    }
+
+    for (entity, stem, gain, _) in stem_q.iter() {
+        commands.entity(entity).despawn();
+        if let Some(audio) = stem_audio.0.get(stem) {
+            spawn_stem(&mut commands, *stem, audio, gain.0, false);
+        }
+    }
+}
+
+///A sink only exists once its audio has loaded, so the stems are started together
+///on the first frame all of them have one
+fn sync_stems(
+    mut commands: Commands,
+    waiting_q: Query<(Entity, &StemWaiting, Option<&AudioSink>), Without<FadeOut>>,
+) {
+    if waiting_q.is_empty() || waiting_q.iter().any(|(_, _, sink)| sink.is_none()) {
+        return;
+    }
+
+    for (entity, waiting, sink) in waiting_q.iter() {
+        if let Some(sink) = sink {
+            sink.play();
+        }
+        commands.entity(entity).remove::<StemWaiting>();
+        if waiting.fade_in {
+            commands.entity(entity).insert(FadeIn::default());
+        }
+    }
+}
+
+///Eases each stem towards the level the bomb calls for.
+///Stems still waiting or fading in keep following too, but their volume is left for the fade to set
+fn mix_stems(
+    time: Res<Time>,
+    bomb: Res<MatchBomb>,
+    audio_settings: Res<AudioSettings>,
+    mut stem_q: Query<
+        (
+            &MusicStem,
+            &mut TrackGain,
+            Option<&mut AudioSink>,
+            Has<FadeIn>,
+            Has<StemWaiting>,
+        ),
+        Without<FadeOut>,
+    >,
+) {
+    let step = (STEM_FOLLOW_RATE * time.delta_secs()).min(1.0);
+    let music = audio_settings.output(AudioBus::Music);
+
+    for (stem, mut gain, sink, fading_in, waiting) in stem_q.iter_mut() {
+        gain.0 += (stem_target(*stem, &bomb) - gain.0) * step;
+        if fading_in || waiting {
+            continue;
+        }
+        if let Some(mut sink) = sink {
+            sink.set_volume(music * Volume::Linear(gain.0));
+        }
+    }
+}
+
+fn play_defuse_stinger(
+    mut commands: Commands,
+    audio_library: Res<AudioLibrary>,
+    audio_settings: Res<AudioSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        AudioPlayer::new(asset_server.load(audio_library.defuse_stinger)),
+        Stinger,
+        AudioBus::Music,
+        PlaybackSettings {
+            mode: bevy::audio::PlaybackMode::Despawn,
+            volume: audio_settings.gain(AudioBus::Music),
+            ..default()
+        },
+    ));
+}
+
+///The explosion cuts every bit of music dead, instead of letting it fade
+fn silence_music(
+    mut commands: Commands,
+    music_q: Query<Entity, Or<(With<MusicTrack>, With<Stinger>)>>,
+) {
+    for entity in music_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,14 +1,147 @@
-use bevy::{platform::collections::HashMap, prelude::*};
+use bevy::{audio::Volume, platform::collections::HashMap, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::synth::{Accidental, Instrument, LoopPattern, Pitch, loop_note};
 // AudioServer Components
 //
 //
 //
 
-#[derive(Resource, Default)]
+/// The quietest a bus gets before it cuts to silence
+const MIN_DECIBELS: f32 = -40.0;
+
+/// The mixer buses every sound plays through. Master scales all of the others
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioBus {
+    Master,
+    Music,
+    Sfx,
+    Ui,
+    Notes,
+}
+
+impl AudioBus {
+    pub const ALL: [AudioBus; 5] = [
+        AudioBus::Master,
+        AudioBus::Music,
+        AudioBus::Sfx,
+        AudioBus::Ui,
+        AudioBus::Notes,
+    ];
+}
+
+/// How loud one bus is set
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BusLevel {
+    /// Slider position from 0.0 to 1.0, mapped to decibels when played
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl BusLevel {
+    const fn new(volume: f32) -> Self {
+        BusLevel {
+            volume,
+            muted: false,
+        }
+    }
+}
+
+/// The one place volumes are kept. The audio server applies them to whatever is playing
+#[derive(Resource, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AudioSettings {
-    pub master_vol: f32,
-    pub music_vol: f32,
-    pub sfx_vol: f32,
+    pub master: BusLevel,
+    pub music: BusLevel,
+    pub sfx: BusLevel,
+    pub ui: BusLevel,
+    pub notes: BusLevel,
+}
+
+impl AudioSettings {
+    /// How far the volume buttons move a slider
+    pub const STEP: f32 = 0.1;
+
+    fn level(&self, bus: AudioBus) -> &BusLevel {
+        match bus {
+            AudioBus::Master => &self.master,
+            AudioBus::Music => &self.music,
+            AudioBus::Sfx => &self.sfx,
+            AudioBus::Ui => &self.ui,
+            AudioBus::Notes => &self.notes,
+        }
+    }
+
+    fn level_mut(&mut self, bus: AudioBus) -> &mut BusLevel {
+        match bus {
+            AudioBus::Master => &mut self.master,
+            AudioBus::Music => &mut self.music,
+            AudioBus::Sfx => &mut self.sfx,
+            AudioBus::Ui => &mut self.ui,
+            AudioBus::Notes => &mut self.notes,
+        }
+    }
+
+    /// The slider position of a bus, clamped in case the save was edited by hand
+    pub fn volume(&self, bus: AudioBus) -> f32 {
+        self.level(bus).volume.clamp(0.0, 1.0)
+    }
+
+    pub fn set_volume(&mut self, bus: AudioBus, volume: f32) {
+        self.level_mut(bus).volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// Moves a volume one step up or down, landing on a whole step
+    pub fn nudge(&mut self, bus: AudioBus, up: bool) {
+        let steps = (self.volume(bus) / Self::STEP).round() + if up { 1.0 } else { -1.0 };
+        self.set_volume(bus, steps * Self::STEP);
+    }
+
+    /// The volume as a whole percentage, for readouts
+    pub fn percent(&self, bus: AudioBus) -> u32 {
+        (self.volume(bus) * 100.0).round() as u32
+    }
+
+    pub fn muted(&self, bus: AudioBus) -> bool {
+        self.level(bus).muted
+    }
+
+    pub fn toggle_mute(&mut self, bus: AudioBus) {
+        let level = self.level_mut(bus);
+        level.muted = !level.muted;
+    }
+
+    /// The gain of one bus on its own. Loudness is heard in decibels,
+    /// so the slider sweeps evenly from MIN_DECIBELS up to full volume
+    pub fn gain(&self, bus: AudioBus) -> Volume {
+        let volume = self.volume(bus);
+        if self.muted(bus) || volume <= 0.0 {
+            Volume::SILENT
+        } else {
+            Volume::Decibels(MIN_DECIBELS * (1.0 - volume))
+        }
+    }
+
+    /// What a sound on the bus plays at, master included
+    pub fn output(&self, bus: AudioBus) -> Volume {
+        match bus {
+            AudioBus::Master => self.gain(AudioBus::Master),
+            _ => self.gain(AudioBus::Master) * self.gain(bus),
+        }
+    }
+}
+
+/// Master at full, the rest near where the old linear 0.3 sat
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: BusLevel::new(1.0),
+            music: BusLevel::new(0.75),
+            sfx: BusLevel::new(0.75),
+            ui: BusLevel::new(0.75),
+            notes: BusLevel::new(0.75),
+        }
+    }
 }
 
 //To add an SFX audio source create a new element
@@ -18,13 +151,6 @@ pub enum EffectName {
     Click,
     UiConfirm,
     ValidSelection,
-    NoteA,
-    NoteB,
-    NoteC,
-    NoteD,
-    NoteE,
-    NoteF,
-    NoteG,
     BubblePop,
     Fuse,
     FanFare,
@@ -32,10 +158,24 @@ pub enum EffectName {
     Negative,
 }
 
+impl EffectName {
+    /// The mixer bus the effect plays through
+    pub fn bus(&self) -> AudioBus {
+        match self {
+            EffectName::Click | EffectName::UiConfirm => AudioBus::Ui,
+            _ => AudioBus::Sfx,
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct AudioLibrary {
     pub sfx: HashMap<EffectName, &'static str>,
     pub music: HashMap<MusicTrackName, &'static str>,
+    pub playlists: HashMap<MusicScene, Playlist>,
+    pub stems: HashMap<MusicStem, StemSource>,
+    /// Played over the stems when a bomb is defused
+    pub defuse_stinger: &'static str,
 }
 
 impl Default for AudioLibrary {
@@ -45,13 +185,6 @@ impl Default for AudioLibrary {
                 (EffectName::Click, "sfx/click.ogg"),
                 (EffectName::UiConfirm, "sfx/sine_boop.ogg"),
                 (EffectName::ValidSelection, "sfx/select.ogg"),
-                (EffectName::NoteA, "sfx/organ_a.ogg"),
-                (EffectName::NoteB, "sfx/organ_b.ogg"),
-                (EffectName::NoteC, "sfx/organ_c.ogg"),
-                (EffectName::NoteD, "sfx/organ_d.ogg"),
-                (EffectName::NoteE, "sfx/organ_e.ogg"),
-                (EffectName::NoteF, "sfx/organ_f.ogg"),
-                (EffectName::NoteG, "sfx/organ_g.ogg"),
                 (EffectName::BubblePop, "sfx/bubble_pop.ogg"),
                 (EffectName::Fuse, "sfx/fuse_quick.ogg"),
                 (EffectName::FanFare, "sfx/ba_bum_fanfare.ogg"),
@@ -60,6 +193,78 @@ impl Default for AudioLibrary {
             ]),
 
             music: HashMap::from([(MusicTrackName::TrackOne, "music/static.ogg")]),
+
+            playlists: HashMap::from([
+                (
+                    MusicScene::Menu,
+                    Playlist::new(PlaylistOrder::Loop, vec![MusicTrackName::TrackOne]),
+                ),
+                (
+                    MusicScene::Game,
+                    Playlist::new(PlaylistOrder::Shuffle, vec![MusicTrackName::TrackOne]),
+                ),
+                (
+                    MusicScene::GameOver,
+                    Playlist::new(PlaylistOrder::Loop, vec![MusicTrackName::TrackOne]),
+                ),
+            ]),
+
+            //The synthesized stems share a tempo and length, so they loop on the same beat.
+            //Both are restarted with the base each time it comes round
+            stems: HashMap::from([
+                (MusicStem::Base, StemSource::File("music/static.ogg")),
+                (
+                    MusicStem::Percussion,
+                    StemSource::Synth(LoopPattern {
+                        tempo: STEM_TEMPO,
+                        beats: STEM_BEATS,
+                        notes: (0..STEM_BEATS)
+                            .flat_map(|beat| {
+                                let beat = beat as f32;
+                                [
+                                    loop_note(
+                                        beat,
+                                        Pitch::new(0, Accidental::Natural, 2),
+                                        Instrument::Pluck,
+                                    ),
+                                    loop_note(
+                                        beat + 0.5,
+                                        Pitch::new(7, Accidental::Natural, 4),
+                                        Instrument::Chiptune,
+                                    ),
+                                ]
+                            })
+                            .collect(),
+                    }),
+                ),
+                (
+                    MusicStem::Tension,
+                    StemSource::Synth(LoopPattern {
+                        tempo: STEM_TEMPO,
+                        beats: STEM_BEATS,
+                        //A tritone, ticking on every other beat
+                        notes: (0..STEM_BEATS)
+                            .step_by(2)
+                            .flat_map(|beat| {
+                                let beat = beat as f32;
+                                [
+                                    loop_note(
+                                        beat,
+                                        Pitch::new(0, Accidental::Natural, 5),
+                                        Instrument::Chime,
+                                    ),
+                                    loop_note(
+                                        beat + 1.0,
+                                        Pitch::new(5, Accidental::Sharp, 5),
+                                        Instrument::Chime,
+                                    ),
+                                ]
+                            })
+                            .collect(),
+                    }),
+                ),
+            ]),
+            defuse_stinger: "sfx/chino_drops_plus.ogg",
         }
     }
 }
@@ -97,20 +302,79 @@ impl SfxSettings {
     }
 }
 
-#[derive(Component, Eq, PartialEq, Hash, Debug)]
+//To add a music track create a new element,
+//define its path in the AudioLibrary Default impl, then list it in a playlist
+#[derive(Component, Eq, PartialEq, Hash, Debug, Clone, Copy)]
 pub enum MusicTrackName {
     TrackOne,
 }
 
+/// The parts of the game that each have their own music
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicScene {
+    Menu,
+    /// The tutorial, which has no bomb to react to
+    Game,
+    /// A standard run, scored by the adaptive stems instead of a playlist
+    Bomb,
+    GameOver,
+}
+
+/// Beats per minute of the synthesized stems
+const STEM_TEMPO: f32 = 120.0;
+/// Length of the synthesized stems, in beats
+const STEM_BEATS: u32 = 8;
+
+/// Where a stem's audio comes from
+#[derive(Debug, Clone)]
+pub enum StemSource {
+    File(&'static str),
+    /// Synthesized, so it is exactly as long as its pattern
+    Synth(LoopPattern),
+}
+
+/// The layers of the adaptive music. They play in sync, each at its own level
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicStem {
+    /// Always on
+    Base,
+    /// Follows how many points are still needed per turn
+    Percussion,
+    /// Rises as the turns run out
+    Tension,
+}
+
+impl MusicStem {
+    pub const ALL: [MusicStem; 3] = [MusicStem::Base, MusicStem::Percussion, MusicStem::Tension];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistOrder {
+    /// Plays the tracks in the order listed, then starts over
+    Loop,
+    /// Plays every track once in a random order, then reshuffles
+    Shuffle,
+}
+
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    pub order: PlaylistOrder,
+    pub tracks: Vec<MusicTrackName>,
+}
+
+impl Playlist {
+    pub fn new(order: PlaylistOrder, tracks: Vec<MusicTrackName>) -> Self {
+        Playlist { order, tracks }
+    }
+}
+
 //When this event is fired the sound associated with the EffectName enum is played.
 #[derive(Event)]
 pub struct PlaySoundEffectEvent(pub SfxSettings);
 
+//When this event is fired the pitch is synthesized on the chosen instrument and played.
 #[derive(Event)]
-pub struct MusicVolumeChangedEvent(pub bool);
-
-#[derive(Event)]
-pub struct MasterVolumeChangedEvent(pub bool);
+pub struct PlayNoteEvent(pub Pitch);
 
 //Marker component for music track
 #[derive(Component)]
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    prelude::*,
+};
+use rand::seq::SliceRandom;
+
+use super::components::*;
+use crate::{
+    game::{GameMode, GameState},
+    screen::components::ScreenState,
+};
+
+const CROSSFADE_SECS: f32 = 2.0;
+
+// Picks the music for whatever part of the game is on screen.
+// Each scene has a playlist in the AudioLibrary. Tracks play once and are despawned,
+// then the next one is queued, so a playlist runs for as long as its scene does.
+// A playlist of one track just loops it, so it doesn't fade back in from silence each time round.
+// A standard run has no playlist, the adaptive stems score it instead.
+pub struct MusicDirectorPlugin;
+
+impl Plugin for MusicDirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MusicDirector::default()).add_systems(
+            Update,
+            (change_music_scene, queue_next_track, fade_music)
+                .chain()
+                .after(super::apply_audio_settings),
+        );
+    }
+}
+
+/// The scene the music is following, and the tracks still to come in its playlist
+#[derive(Resource, Debug, Default)]
+pub struct MusicDirector {
+    scene: Option<MusicScene>,
+    /// Next track last, so it can be popped
+    queue: Vec<MusicTrackName>,
+}
+
+impl MusicDirector {
+    pub fn scene(&self) -> Option<MusicScene> {
+        self.scene
+    }
+
+    fn next_track(&mut self, playlist: &Playlist) -> Option<MusicTrackName> {
+        if self.queue.is_empty() {
+            self.queue = playlist.tracks.iter().rev().copied().collect();
+            if playlist.order == PlaylistOrder::Shuffle {
+                self.queue.shuffle(&mut rand::rng());
+            }
+        }
+        self.queue.pop()
+    }
+}
+
+/// Brings a new track up from silence
+#[derive(Component)]
+pub struct FadeIn(Timer);
+
+impl Default for FadeIn {
+    fn default() -> Self {
+        FadeIn(crossfade_timer())
+    }
+}
+
+/// Takes an outgoing track down to silence, then despawns it
+#[derive(Component)]
+pub struct FadeOut(Timer);
+
+/// How loud a track plays relative to the music bus. Tracks without one play at full
+#[derive(Component, Debug)]
+pub struct TrackGain(pub f32);
+
+fn crossfade_timer() -> Timer {
+    Timer::new(Duration::from_secs_f32(CROSSFADE_SECS), TimerMode::Once)
+}
+
+/// Which music fits the current screen. The splash screen stays quiet
+fn music_scene(screen: &ScreenState, game: &GameState, mode: &GameMode) -> Option<MusicScene> {
+    match (screen, game, mode) {
+        (ScreenState::Splash | ScreenState::Exit, _, _) => None,
+        (ScreenState::Game, GameState::GameOver, _) => Some(MusicScene::GameOver),
+        (ScreenState::Game, _, GameMode::Standard) => Some(MusicScene::Bomb),
+        (ScreenState::Game, _, GameMode::Tutorial) => Some(MusicScene::Game),
+        _ => Some(MusicScene::Menu),
+    }
+}
+
+//Systems
+
+///Fades out whatever is playing when the scene changes, and starts the new scene's playlist over
+pub(super) fn change_music_scene(
+    mut commands: Commands,
+    screen: Res<State<ScreenState>>,
+    game: Res<State<GameState>>,
+    mode: Res<State<GameMode>>,
+    mut director: ResMut<MusicDirector>,
+    track_q: Query<Entity, (With<MusicTrack>, Without<FadeOut>)>,
+) {
+    let scene = music_scene(screen.get(), game.get(), mode.get());
+    if scene == director.scene {
+        return;
+    }
+    director.scene = scene;
+    director.queue.clear();
+
+    for entity in track_q.iter() {
+        commands
+            .entity(entity)
+            .remove::<FadeIn>()
+            .insert(FadeOut(crossfade_timer()));
+    }
+}
+
+///Starts the next track of the playlist once nothing but outgoing tracks are left
+fn queue_next_track(
+    mut commands: Commands,
+    mut director: ResMut<MusicDirector>,
+    audio_library: Res<AudioLibrary>,
+    asset_server: Res<AssetServer>,
+    track_q: Query<(), (With<MusicTrack>, Without<FadeOut>)>,
+) {
+    if !track_q.is_empty() {
+        return;
+    }
+    let Some(playlist) = director
+        .scene
+        .and_then(|scene| audio_library.playlists.get(&scene))
+    else {
+        return;
+    };
+    let Some(track) = director.next_track(playlist) else {
+        return;
+    };
+    let Some(path) = audio_library.music.get(&track) else {
+        warn!("No path for music track {track:?}");
+        return;
+    };
+    let mode = if playlist.tracks.len() == 1 {
+        PlaybackMode::Loop
+    } else {
+        PlaybackMode::Despawn
+    };
+
+    commands.spawn((
+        AudioPlayer::new(asset_server.load(*path)),
+        MusicTrack,
+        track,
+        AudioBus::Music,
+        FadeIn::default(),
+        PlaybackSettings {
+            mode,
+            volume: Volume::SILENT,
+            ..default()
+        },
+    ));
+}
+
+///Ramps tracks between silence and the music volume.
+///Timers only run once a track's sink exists, so a slow load still gets its whole fade in
+pub(super) fn fade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    audio_settings: Res<AudioSettings>,
+    mut fade_in_q: Query<
+        (Entity, &mut FadeIn, &mut AudioSink, Option<&TrackGain>),
+        Without<FadeOut>,
+    >,
+    mut fade_out_q: Query<(Entity, &mut FadeOut, &mut AudioSink, Option<&TrackGain>)>,
+) {
+    let music = audio_settings.output(AudioBus::Music);
+    let full = |gain: Option<&TrackGain>| music * Volume::Linear(gain.map_or(1.0, |gain| gain.0));
+
+    for (entity, mut fade, mut sink, gain) in fade_in_q.iter_mut() {
+        fade.0.tick(time.delta());
+        sink.set_volume(full(gain) * Volume::Linear(fade.0.fraction()));
+        if fade.0.finished() {
+            commands.entity(entity).remove::<FadeIn>();
+        }
+    }
+
+    for (entity, mut fade, mut sink, gain) in fade_out_q.iter_mut() {
+        fade.0.tick(time.delta());
+        sink.set_volume(full(gain) * Volume::Linear(fade.0.fraction_remaining()));
+        if fade.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,514 @@
+use std::{f64::consts::TAU, sync::Arc, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::components::{AudioBus, AudioSettings, PlayNoteEvent, SoundEffect};
+use crate::save;
+
+const SAVE_NAME: &str = "notes";
+const SAMPLE_RATE: u32 = 44_100;
+/// Headroom, so chords of several notes don't clip
+const NOTE_GAIN: f32 = 0.5;
+
+// Piece notes are synthesized rather than loaded, so any pitch on any instrument can be played.
+// The samples are computed from their index alone, which keeps every note in tune for its whole length,
+// and needs nothing beyond plain Rust, so it runs the same on native and wasm.
+pub struct SynthPlugin;
+
+impl Plugin for SynthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<SynthNote>()
+            .add_audio_source::<SynthLoop>()
+            .add_event::<PlayNoteEvent>()
+            .insert_resource(SynthCache::default())
+            .insert_resource(save::load::<NoteSettings>(SAVE_NAME))
+            .add_systems(Update, play_notes)
+            .add_systems(
+                Update,
+                save_note_settings.run_if(resource_changed::<NoteSettings>),
+            );
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Accidental {
+    Flat,
+    #[default]
+    Natural,
+    Sharp,
+}
+
+impl Accidental {
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Accidental::Flat => "accidental.flat",
+            Accidental::Natural => "accidental.natural",
+            Accidental::Sharp => "accidental.sharp",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Accidental::Flat => Accidental::Natural,
+            Accidental::Natural => Accidental::Sharp,
+            Accidental::Sharp => Accidental::Flat,
+        }
+    }
+
+    const fn semitones(&self) -> i16 {
+        match self {
+            Accidental::Flat => -1,
+            Accidental::Natural => 0,
+            Accidental::Sharp => 1,
+        }
+    }
+}
+
+/// A note as a MIDI number, where 60 is middle C and 69 is concert A
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pitch(i16);
+
+impl Pitch {
+    /// The octave with middle C. Notes are written in it, and the octave setting moves them from there
+    pub const MIDDLE_OCTAVE: i16 = 4;
+
+    /// Builds a pitch from its semitone above C (0 to 11), an accidental and an octave
+    pub const fn new(semitone: i16, accidental: Accidental, octave: i16) -> Self {
+        Pitch((octave + 1) * 12 + semitone + accidental.semitones())
+    }
+
+    pub fn transposed(&self, semitones: i16) -> Self {
+        Pitch(self.0 + semitones)
+    }
+
+    /// Equal temperament, tuned to A at 440 Hz
+    pub fn frequency(&self) -> f32 {
+        440.0 * 2f32.powf((self.0 - 69) as f32 / 12.0)
+    }
+}
+
+/// A sine at some multiple of the note's frequency
+#[derive(Debug, Clone, Copy)]
+pub struct Partial {
+    pub ratio: f32,
+    pub amplitude: f32,
+}
+
+const fn partial(ratio: f32, amplitude: f32) -> Partial {
+    Partial { ratio, amplitude }
+}
+
+/// How loud a note is over its life. Times are in seconds
+#[derive(Debug, Clone, Copy)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+    /// Level held after the decay, from 0.0 to 1.0
+    pub sustain: f32,
+    /// How long the sustain is held before the release
+    pub hold: f32,
+    pub release: f32,
+}
+
+impl Envelope {
+    pub fn duration(&self) -> f32 {
+        self.attack + self.decay + self.hold + self.release
+    }
+
+    fn level(&self, time: f32) -> f32 {
+        let mut time = time;
+        if time < self.attack {
+            return time / self.attack;
+        }
+        time -= self.attack;
+        if time < self.decay {
+            return 1.0 - (1.0 - self.sustain) * time / self.decay;
+        }
+        time -= self.decay;
+        if time < self.hold {
+            return self.sustain;
+        }
+        time -= self.hold;
+        if time < self.release {
+            return self.sustain * (1.0 - time / self.release);
+        }
+        0.0
+    }
+}
+
+/// The sounds a piece note can be played with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Instrument {
+    #[default]
+    Organ,
+    Chime,
+    Pluck,
+    Chiptune,
+}
+
+impl Instrument {
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Instrument::Organ => "instrument.organ",
+            Instrument::Chime => "instrument.chime",
+            Instrument::Pluck => "instrument.pluck",
+            Instrument::Chiptune => "instrument.chiptune",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Instrument::Organ => Instrument::Chime,
+            Instrument::Chime => Instrument::Pluck,
+            Instrument::Pluck => Instrument::Chiptune,
+            Instrument::Chiptune => Instrument::Organ,
+        }
+    }
+
+    fn partials(&self) -> Vec<Partial> {
+        match self {
+            Instrument::Organ => vec![
+                partial(1.0, 1.0),
+                partial(2.0, 0.5),
+                partial(3.0, 0.3),
+                partial(4.0, 0.2),
+            ],
+            // The 2.76 overtone is what makes a bar ring like metal
+            Instrument::Chime => vec![partial(1.0, 1.0), partial(2.76, 0.4), partial(5.4, 0.2)],
+            // A sawtooth, built up from its first harmonics
+            Instrument::Pluck => (1..=8).map(|n| partial(n as f32, 1.0 / n as f32)).collect(),
+            // A square wave has only the odd harmonics
+            Instrument::Chiptune => [1, 3, 5, 7, 9]
+                .into_iter()
+                .map(|n| partial(n as f32, 1.0 / n as f32))
+                .collect(),
+        }
+    }
+
+    fn envelope(&self) -> Envelope {
+        match self {
+            Instrument::Organ => Envelope {
+                attack: 0.02,
+                decay: 0.1,
+                sustain: 0.8,
+                hold: 0.35,
+                release: 0.25,
+            },
+            Instrument::Chime => Envelope {
+                attack: 0.002,
+                decay: 1.2,
+                sustain: 0.0,
+                hold: 0.0,
+                release: 0.0,
+            },
+            Instrument::Pluck => Envelope {
+                attack: 0.003,
+                decay: 0.45,
+                sustain: 0.0,
+                hold: 0.0,
+                release: 0.0,
+            },
+            Instrument::Chiptune => Envelope {
+                attack: 0.005,
+                decay: 0.05,
+                sustain: 0.6,
+                hold: 0.2,
+                release: 0.08,
+            },
+        }
+    }
+}
+
+/// How piece notes are played
+#[derive(Resource, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteSettings {
+    pub instrument: Instrument,
+    octave: i16,
+    /// Sharpens or flattens every piece note
+    pub accidental: Accidental,
+}
+
+impl NoteSettings {
+    const MIN_OCTAVE: i16 = 2;
+    const MAX_OCTAVE: i16 = 6;
+
+    pub fn octave(&self) -> i16 {
+        self.octave.clamp(Self::MIN_OCTAVE, Self::MAX_OCTAVE)
+    }
+
+    pub fn raise_octave(&mut self) {
+        self.octave = (self.octave() + 1).min(Self::MAX_OCTAVE);
+    }
+
+    pub fn lower_octave(&mut self) {
+        self.octave = (self.octave() - 1).max(Self::MIN_OCTAVE);
+    }
+
+    /// Moves a pitch written in the middle octave into the chosen one, with the chosen accidental
+    fn place(&self, pitch: Pitch) -> Pitch {
+        pitch.transposed((self.octave() - Pitch::MIDDLE_OCTAVE) * 12 + self.accidental.semitones())
+    }
+}
+
+impl Default for NoteSettings {
+    fn default() -> Self {
+        NoteSettings {
+            instrument: Instrument::default(),
+            octave: Pitch::MIDDLE_OCTAVE,
+            accidental: Accidental::default(),
+        }
+    }
+}
+
+/// A synthesized note, played through an AudioPlayer like any other sound
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct SynthNote {
+    pub frequency: f32,
+    pub partials: Vec<Partial>,
+    pub envelope: Envelope,
+}
+
+impl SynthNote {
+    pub fn new(pitch: Pitch, instrument: Instrument) -> Self {
+        SynthNote {
+            frequency: pitch.frequency(),
+            partials: instrument.partials(),
+            envelope: instrument.envelope(),
+        }
+    }
+}
+
+impl Decodable for SynthNote {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        //Partials above the Nyquist frequency would alias, so they are left out
+        let nyquist = SAMPLE_RATE as f32 / 2.0;
+        let partials: Vec<_> = self
+            .partials
+            .iter()
+            .filter(|partial| self.frequency * partial.ratio < nyquist)
+            .collect();
+        let total: f32 = partials.iter().map(|partial| partial.amplitude).sum();
+
+        SynthDecoder {
+            steps: partials
+                .iter()
+                .map(|partial| {
+                    (
+                        (self.frequency * partial.ratio) as f64 / SAMPLE_RATE as f64,
+                        partial.amplitude / total.max(f32::EPSILON),
+                    )
+                })
+                .collect(),
+            envelope: self.envelope,
+            index: 0,
+            length: (self.envelope.duration() * SAMPLE_RATE as f32).ceil() as u64,
+        }
+    }
+}
+
+/// Yields the samples of a SynthNote, mono at SAMPLE_RATE
+pub struct SynthDecoder {
+    /// Cycles per sample and amplitude of each partial
+    steps: Vec<(f64, f32)>,
+    envelope: Envelope,
+    index: u64,
+    length: u64,
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.index >= self.length {
+            return None;
+        }
+        let level = self.envelope.level(self.index as f32 / SAMPLE_RATE as f32);
+        //Phase from the index rather than a running sum, so rounding never builds up
+        let wave: f32 = self
+            .steps
+            .iter()
+            .map(|(step, amplitude)| {
+                let phase = (step * self.index as f64).fract();
+                (phase * TAU).sin() as f32 * amplitude
+            })
+            .sum();
+        self.index += 1;
+        Some(wave * level * NOTE_GAIN)
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.length - self.index) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f64(
+            self.length as f64 / SAMPLE_RATE as f64,
+        ))
+    }
+}
+
+/// A note in a loop pattern, starting some number of beats in
+#[derive(Debug, Clone, Copy)]
+pub struct LoopNote {
+    pub beat: f32,
+    pub pitch: Pitch,
+    pub instrument: Instrument,
+}
+
+pub const fn loop_note(beat: f32, pitch: Pitch, instrument: Instrument) -> LoopNote {
+    LoopNote {
+        beat,
+        pitch,
+        instrument,
+    }
+}
+
+/// Notes laid out over a number of beats, to be played on repeat
+#[derive(Debug, Clone)]
+pub struct LoopPattern {
+    /// Beats per minute
+    pub tempo: f32,
+    pub beats: u32,
+    pub notes: Vec<LoopNote>,
+}
+
+impl LoopPattern {
+    fn length(&self) -> usize {
+        (self.beats as f32 * 60.0 / self.tempo * SAMPLE_RATE as f32).round() as usize
+    }
+}
+
+/// A synthesized loop, rendered once up front so every repeat is the same number of samples
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct SynthLoop {
+    samples: Arc<[f32]>,
+}
+
+impl SynthLoop {
+    pub fn render(pattern: &LoopPattern) -> Self {
+        let length = pattern.length().max(1);
+        let mut samples = vec![0.0; length];
+        let samples_per_beat = length as f32 / pattern.beats.max(1) as f32;
+
+        for note in pattern.notes.iter() {
+            let start = (note.beat * samples_per_beat).round() as usize;
+            //Tails that run past the end wrap round to the start, so the seam can't be heard
+            for (index, sample) in SynthNote::new(note.pitch, note.instrument)
+                .decoder()
+                .enumerate()
+            {
+                samples[(start + index) % length] += sample;
+            }
+        }
+
+        SynthLoop {
+            samples: samples.into(),
+        }
+    }
+}
+
+impl Decodable for SynthLoop {
+    type DecoderItem = f32;
+    type Decoder = SynthLoopDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthLoopDecoder {
+            samples: self.samples.clone(),
+            index: 0,
+        }
+    }
+}
+
+/// Yields the samples of a SynthLoop once through, mono at SAMPLE_RATE
+pub struct SynthLoopDecoder {
+    samples: Arc<[f32]>,
+    index: usize,
+}
+
+impl Iterator for SynthLoopDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.index).copied();
+        self.index += 1;
+        sample
+    }
+}
+
+impl Source for SynthLoopDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len().saturating_sub(self.index))
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f64(
+            self.samples.len() as f64 / SAMPLE_RATE as f64,
+        ))
+    }
+}
+
+/// Notes already synthesized, so hovering the same piece again reuses the asset
+#[derive(Resource, Default)]
+struct SynthCache(HashMap<(Pitch, Instrument), Handle<SynthNote>>);
+
+//Systems
+
+fn play_notes(
+    mut commands: Commands,
+    mut er_play_note: EventReader<PlayNoteEvent>,
+    note_settings: Res<NoteSettings>,
+    audio_settings: Res<AudioSettings>,
+    mut cache: ResMut<SynthCache>,
+    mut notes: ResMut<Assets<SynthNote>>,
+) {
+    for event in er_play_note.read() {
+        let pitch = note_settings.place(event.0);
+        let instrument = note_settings.instrument;
+        let handle = cache
+            .0
+            .entry((pitch, instrument))
+            .or_insert_with(|| notes.add(SynthNote::new(pitch, instrument)))
+            .clone();
+
+        commands.spawn((
+            AudioPlayer(handle),
+            PlaybackSettings {
+                volume: audio_settings.gain(AudioBus::Notes),
+                ..default()
+            },
+            SoundEffect,
+            AudioBus::Notes,
+        ));
+    }
+}
+
+fn save_note_settings(settings: Res<NoteSettings>) {
+    save::save(SAVE_NAME, &*settings);
+}
@@ -10,7 +10,8 @@ pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_camera)
-            .add_systems(OnEnter(ScreenState::Game), swap_camera_system);
+            .add_systems(OnEnter(ScreenState::Game), swap_camera_system)
+            .add_systems(OnExit(ScreenState::Game), setup_camera);
     }
 }
 
@@ -45,6 +46,7 @@ fn swap_camera_system(mut commands: Commands, camera: Single<Entity, With<MainCa
             ..default()
         },
         MainCamera,
+        StateScoped(ScreenState::Game),
     ));
 }
 
@@ -1,11 +1,18 @@
 use crate::{
     audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings},
-    game::cell_line::CellLinePlugin,
-    screen::{components::*, menu::UiRoot},
+    game::{
+        attributes::AttributeRegistry,
+        cell_line::{CapturedArea, CellLine, CellLinePlugin},
+    },
+    locale::LocalizedText,
+    screen::{components::*, menu::create_button_node},
+    ui_theme::UiTheme,
 };
-use bevy::prelude::*;
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
 pub struct GamePlugin;
 
+pub mod attributes;
+
 pub mod game_grid;
 use game_grid::*;
 
@@ -23,10 +30,33 @@ use points::*;
 pub mod bomb;
 use bomb::*;
 
+pub mod rules;
+use rules::*;
+
+pub mod statistics;
+use statistics::*;
+
+pub mod tutorial;
+use tutorial::*;
+
+pub mod pause;
+use pause::*;
+
+pub mod cursor;
+use cursor::*;
+
+pub mod palette;
+use palette::*;
+
+pub mod inspector;
+use inspector::*;
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.insert_state(GameState::FreePick)
+            .insert_state(GameMode::Standard)
             .insert_resource(ClearColor(Color::BLACK))
+            .init_resource::<GameRules>()
             .add_plugins(GameGridPlugin)
             .add_plugins((
                 GameBoardPlugin,
@@ -34,19 +64,60 @@ impl Plugin for GamePlugin {
                 CellLinePlugin,
                 PointsPlugin,
                 BombPlugin,
+                StatisticsPlugin,
+                TutorialPlugin,
+                PausePlugin,
+                CursorPlugin,
+                PalettePlugin,
+                InspectorPlugin,
             ))
-            .add_systems(OnEnter(ScreenState::Game), setup_game)
+            .add_event::<NewGameEvent>()
+            .init_schedule(SpawnHud)
+            .add_systems(OnEnter(ScreenState::Game), (setup_game, spawn_hud))
             .add_systems(OnEnter(GameState::GameOver), game_over)
-            .add_systems(Update, game_won.run_if(in_state(ScreenState::Game)));
+            .add_systems(Update, game_won.run_if(in_state(ScreenState::Game)))
+            .add_systems(
+                Update,
+                (reset_game, rebuild_board, spawn_hud)
+                    .chain()
+                    .run_if(on_event::<NewGameEvent>),
+            )
+            .add_systems(
+                Update,
+                game_over_buttons_system.run_if(in_state(GameState::GameOver)),
+            )
+            .add_systems(OnExit(ScreenState::Game), reset_game);
     }
 }
 
+//Marker for every HUD element, so a new game can rebuild them
 #[derive(Component)]
 pub struct HudDisplay;
 
+/// Spawns the HUD. Runs when the game screen opens and again whenever a new game starts
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpawnHud;
+
+/// Starts a fresh run without leaving the game screen
+#[derive(Event, Default)]
+pub struct NewGameEvent {
+    /// Deal the same starting board as the last run
+    pub same_seed: bool,
+}
+
 //Marker for the gameover model
 #[derive(Component)]
 pub struct GameOverText;
+
+//Marker for the retry buttons shown on game over
+#[derive(Component)]
+pub struct GameOverMenu;
+
+#[derive(Component, Debug)]
+pub enum GameOverButtonType {
+    Retry,
+    ReplayBoard,
+}
 #[derive(States, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum GameState {
     FreePick,
@@ -54,20 +125,23 @@ pub enum GameState {
     GameOver,
 }
 
-pub fn setup_game(
-    mut commands: Commands,
-    mut ew_initgrid: EventWriter<InitializeGridEvent>,
-    uiroot: Single<Entity, With<UiRoot>>,
-) {
+/// Whether the game screen is a normal run or the scripted tutorial
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    Standard,
+    Tutorial,
+}
+
+pub fn setup_game(mut ew_initgrid: EventWriter<InitializeGridEvent>) {
     info!("Initializing Game");
 
-    commands.entity(*uiroot).despawn();
     ew_initgrid.write_default();
 }
 
 pub fn game_over(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
     mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
 ) {
     let model = asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/game_over.glb"));
@@ -76,12 +150,43 @@ pub fn game_over(
             SceneRoot(model.clone()),
             Transform::from_translation(Vec3::new(7.7, 7.7, 5.0)),
             GameOverText,
+            StateScoped(ScreenState::Game),
             Pickable {
                 should_block_lower: true,
                 ..default()
             },
         ))
         .observe(on_retry);
+
+    let menu = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::VMin(10.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GameOverMenu,
+            StateScoped(ScreenState::Game),
+        ))
+        .id();
+    let retry_button = create_button_node(
+        GameOverButtonType::Retry,
+        LocalizedText::new("game_over.retry"),
+        &mut commands,
+        &theme,
+    );
+    let replay_button = create_button_node(
+        GameOverButtonType::ReplayBoard,
+        LocalizedText::new("game_over.replay_board"),
+        &mut commands,
+        &theme,
+    );
+    commands
+        .entity(menu)
+        .add_children(&[retry_button, replay_button]);
+
     ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
         EffectName::Detonation,
         None,
@@ -94,6 +199,7 @@ pub fn game_won(
     mut bomb: ResMut<MatchBomb>,
     mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
     mut ew_bomb_defused: EventWriter<BombDefusedEvent>,
+    mode: Res<State<GameMode>>,
     materials: ResMut<Assets<StandardMaterial>>,
 ) {
     if bomb.points_remaining() == 0 {
@@ -109,16 +215,95 @@ pub fn game_won(
             None,
             None,
         )));
-        ew_bomb_defused.write_default();
+        //The tutorial still plays the defuse, but it doesn't count towards stats or achievements
+        if *mode.get() == GameMode::Standard {
+            ew_bomb_defused.write_default();
+        }
     }
 }
 
-fn on_retry(
-    trigger: Trigger<Pointer<Click>>,
-    mut commands: Commands,
+fn on_retry(_trigger: Trigger<Pointer<Click>>, mut ew_new_game: EventWriter<NewGameEvent>) {
+    ew_new_game.write_default();
+}
+
+fn game_over_buttons_system(
+    mut interaction_q: Query<
+        (&Interaction, &mut BackgroundColor, &GameOverButtonType),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut ew_new_game: EventWriter<NewGameEvent>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    theme: Res<UiTheme>,
+) {
+    for (interaction, mut bgcolor, button_type) in &mut interaction_q {
+        match *interaction {
+            Interaction::Pressed => {
+                *bgcolor = theme.button_background_pressed.into();
+                ew_new_game.write(NewGameEvent {
+                    same_seed: matches!(button_type, GameOverButtonType::ReplayBoard),
+                });
+                ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+                    EffectName::UiConfirm,
+                    Some(false),
+                    None,
+                )));
+            }
+            Interaction::Hovered => *bgcolor = theme.button_background_hover.into(),
+            Interaction::None => *bgcolor = theme.button_background_normal.into(),
+        }
+    }
+}
+
+fn spawn_hud(world: &mut World) {
+    world.run_schedule(SpawnHud);
+}
+
+///Puts every resource a run touches back to its starting value
+fn reset_game(
     mut bomb: ResMut<MatchBomb>,
-    game_over: Single<Entity, With<GameOverText>>,
+    mut grid: ResMut<GameGrid>,
+    mut score: ResMut<Score>,
+    mut cell_line: ResMut<CellLine>,
+    mut captured: ResMut<CapturedArea>,
+    mut statistics: ResMut<Statistics>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+    mut pending_specials: ResMut<PendingSpecials>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut seed: ResMut<BoardSeed>,
+    mut er_new_game: EventReader<NewGameEvent>,
+    registry: Res<AttributeRegistry>,
 ) {
+    info!("Resetting game");
     bomb.reset();
-    commands.entity(*game_over).despawn();
+    score.reset();
+    cell_line.clear();
+    captured.cells.clear();
+    statistics.start_run();
+    despawn_queue.0.clear();
+    pending_specials.0.clear();
+    if !er_new_game.read().any(|event| event.same_seed) {
+        *seed = BoardSeed::random();
+    }
+    *grid = GameGrid::seeded(&registry, seed.0);
+    next_state.set(GameState::FreePick);
+}
+
+///Clears the HUD and game over screen and rebuilds the board from the reset grid
+fn rebuild_board(
+    mut commands: Commands,
+    mut ew_update_board: EventWriter<UpdateBoardEvent>,
+    run_q: Query<
+        Entity,
+        Or<(
+            With<GameOverText>,
+            With<GameOverMenu>,
+            With<RunSummaryDisplay>,
+            With<HudDisplay>,
+        )>,
+    >,
+) {
+    for entity in run_q.iter() {
+        commands.entity(entity).despawn();
+    }
+    ew_update_board.write_default();
 }
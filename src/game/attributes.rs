@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use bevy::prelude::*;
+use rand::distr::{Distribution, weighted::WeightedIndex};
+use rand::prelude::*;
+
+use crate::{
+    audio_server::synth::Pitch,
+    game::game_grid::{PieceColor, PieceShape, PieceSound},
+};
+
+// The attributes pieces are matched on.
+// To add a new attribute create a new element here, implement PieceAttribute
+// for its value type, then register it in the AttributeRegistry Default impl
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeKind {
+    Color,
+    Shape,
+    Sound,
+}
+
+impl AttributeKind {
+    pub const COUNT: usize = 3;
+
+    pub const ALL: [AttributeKind; AttributeKind::COUNT] = [
+        AttributeKind::Color,
+        AttributeKind::Shape,
+        AttributeKind::Sound,
+    ];
+
+    /// Slot of this attribute in a piece's attribute array
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// A set of values pieces can be dealt for one attribute
+pub trait PieceAttribute:
+    Debug + Clone + Copy + PartialEq + Eq + Hash + Send + Sync + 'static
+{
+    const KIND: AttributeKind;
+
+    /// Every value a piece can be dealt, in registry order
+    const VALUES: &'static [Self];
+
+    fn name(&self) -> &'static str;
+
+    fn index(&self) -> usize {
+        Self::VALUES
+            .iter()
+            .position(|value| value == self)
+            .unwrap_or_else(|| panic!("{} is missing from its attribute's VALUES", self.name()))
+    }
+
+    fn from_index(index: usize) -> Self {
+        Self::VALUES[index % Self::VALUES.len()]
+    }
+}
+
+/// How a value shows up on a board piece
+#[derive(Debug, Clone)]
+pub enum AttributeRender {
+    /// Tints the piece material
+    Tint(Color),
+    /// Picks the model the piece is spawned with
+    Model(&'static str),
+    /// Plays a note when the piece is hovered
+    Note(Pitch),
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeValue {
+    pub name: &'static str,
+    /// Relative chance of a piece being dealt this value
+    pub weight: f32,
+    pub render: AttributeRender,
+}
+
+/// A glyph drawn around the head of a strict line
+#[derive(Debug, Clone)]
+pub enum LockGlyph {
+    /// Circles with these radii
+    Rings(Vec<f32>),
+    /// A square with sides this long
+    Square(f32),
+}
+
+/// How a strict line shows that this attribute is the one locked
+#[derive(Debug, Clone)]
+pub struct LockMarker {
+    /// Also the color the line starts from
+    pub color: Color,
+    pub glyph: LockGlyph,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeDef {
+    pub kind: AttributeKind,
+    pub name: &'static str,
+    pub values: Vec<AttributeValue>,
+    pub lock: LockMarker,
+    sampler: WeightedIndex<f32>,
+}
+
+/// Every attribute a piece has, how often each value is dealt, and how it is presented
+#[derive(Resource, Debug, Clone)]
+pub struct AttributeRegistry {
+    defs: HashMap<AttributeKind, AttributeDef>,
+}
+
+impl AttributeRegistry {
+    pub fn empty() -> Self {
+        AttributeRegistry {
+            defs: HashMap::new(),
+        }
+    }
+
+    /// Registers every value of `T`, taking its weight and presentation from `describe`
+    pub fn register<T: PieceAttribute>(
+        &mut self,
+        name: &'static str,
+        lock: LockMarker,
+        describe: impl Fn(T) -> (f32, AttributeRender),
+    ) -> &mut Self {
+        let values: Vec<AttributeValue> = T::VALUES
+            .iter()
+            .map(|value| {
+                let (weight, render) = describe(*value);
+                AttributeValue {
+                    name: value.name(),
+                    weight,
+                    render,
+                }
+            })
+            .collect();
+
+        let sampler = WeightedIndex::new(values.iter().map(|value| value.weight))
+            .expect("an attribute needs at least one value with a positive weight");
+
+        self.defs.insert(
+            T::KIND,
+            AttributeDef {
+                kind: T::KIND,
+                name,
+                values,
+                lock,
+                sampler,
+            },
+        );
+        self
+    }
+
+    pub fn def(&self, kind: AttributeKind) -> &AttributeDef {
+        self.defs
+            .get(&kind)
+            .unwrap_or_else(|| panic!("{kind:?} was never registered"))
+    }
+
+    /// Number of values pieces can be dealt for this attribute
+    pub fn count(&self, kind: AttributeKind) -> usize {
+        self.def(kind).values.len()
+    }
+
+    /// Picks a value index for this attribute using the registered weights
+    pub fn sample<R: Rng + ?Sized>(&self, kind: AttributeKind, rng: &mut R) -> usize {
+        self.def(kind).sampler.sample(rng)
+    }
+
+    pub fn value<T: PieceAttribute>(&self, value: T) -> &AttributeValue {
+        &self.def(T::KIND).values[value.index()]
+    }
+
+    pub fn value_at(&self, kind: AttributeKind, index: usize) -> &AttributeValue {
+        &self.def(kind).values[index]
+    }
+}
+
+impl Default for AttributeRegistry {
+    fn default() -> Self {
+        let mut registry = AttributeRegistry::empty();
+        registry
+            .register::<PieceColor>(
+                "Color",
+                LockMarker {
+                    color: Color::srgba(5.0, 4.0, 0.0, 1.0),
+                    glyph: LockGlyph::Rings(vec![0.9]),
+                },
+                |color| (1.0, AttributeRender::Tint(color.to_color())),
+            )
+            .register::<PieceShape>(
+                "Shape",
+                LockMarker {
+                    color: Color::srgba(0.0, 4.0, 5.0, 1.0),
+                    glyph: LockGlyph::Square(1.6),
+                },
+                |shape| (1.0, AttributeRender::Model(shape.model_path())),
+            )
+            .register::<PieceSound>(
+                "Sound",
+                LockMarker {
+                    color: Color::srgba(0.5, 5.0, 0.5, 1.0),
+                    glyph: LockGlyph::Rings(vec![0.6, 0.9, 1.2]),
+                },
+                |sound| (1.0, AttributeRender::Note(sound.pitch())),
+            );
+        registry
+    }
+}
@@ -3,8 +3,10 @@ use std::{f32::consts::TAU, time::Duration};
 use bevy::prelude::*;
 
 use crate::{
-    game::GameState,
+    game::{GameMode, GameState, HudDisplay, SpawnHud, pause::PauseState},
+    locale::LocalizedText,
     screen::components::ScreenState,
+    ui_theme::UiTheme,
 };
 
 const THRESHOLD_INCREMENT: u64 = 250;
@@ -15,17 +17,24 @@ impl Plugin for BombPlugin {
         app.add_event::<BombDefusedEvent>()
             .insert_resource(MatchBomb::default())
             .add_systems(
-                OnEnter(ScreenState::Game),
+                SpawnHud,
                 (setup_countdown_display, setup_defused_count_display),
             )
             .add_systems(
                 Update,
                 (update_countdown_display, update_defused_count_display),
             )
-            .add_systems(Update, explode_bomb.run_if(in_state(ScreenState::Game)))
             .add_systems(
                 Update,
-                (text_despawn_timer, rotate_bomb).run_if(in_state(ScreenState::Game)),
+                explode_bomb
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(GameMode::Standard)),
+            )
+            .add_systems(
+                Update,
+                (text_despawn_timer, rotate_bomb)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(PauseState::Running)),
             )
             .add_systems(Update, on_defuse.run_if(on_event::<BombDefusedEvent>));
     }
@@ -53,7 +62,11 @@ impl MatchBomb {
     }
 
     pub fn decrement(&mut self) {
-        self.turns_remaining = self.turns_remaining.saturating_sub(1);
+        self.decrement_by(1);
+    }
+
+    pub fn decrement_by(&mut self, turns: u64) {
+        self.turns_remaining = self.turns_remaining.saturating_sub(turns);
     }
 
     pub fn points_remaining(&self) -> u64 {
@@ -64,12 +77,22 @@ impl MatchBomb {
         self.turns_remaining
     }
 
+    pub fn defused_count(&self) -> u64 {
+        self.defused_count
+    }
+
     pub fn rearm(&mut self) {
         self.defused_count += 1;
         self.turns_remaining = 5;
         self.point_threshold = THRESHOLD_INCREMENT + (THRESHOLD_INCREMENT * self.defused_count);
     }
 
+    /// Arms the bomb with a custom countdown, used by scripted boards
+    pub fn arm(&mut self, turns: u64, points: u64) {
+        self.turns_remaining = turns;
+        self.point_threshold = points;
+    }
+
     pub fn reset(&mut self) {
         self.defused_count = 0;
         self.turns_remaining = 5;
@@ -105,7 +128,7 @@ impl Default for TextDespawn {
 pub struct BombDefusedEvent;
 //Systems
 
-fn setup_defused_count_display(mut commads: Commands) {
+fn setup_defused_count_display(mut commads: Commands, theme: Res<UiTheme>) {
     commads.spawn((
         Node {
             position_type: PositionType::Absolute,
@@ -115,11 +138,10 @@ fn setup_defused_count_display(mut commads: Commands) {
             ..default()
         },
         DefusedCountDisplay,
-        Text::new("0"),
-        TextFont {
-            font_size: 24.0,
-            ..default()
-        },
+        HudDisplay,
+        StateScoped(ScreenState::Game),
+        LocalizedText::new("hud.bombs_defused").count(0),
+        theme.hud_text_style(),
     ));
 }
 
@@ -127,6 +149,7 @@ fn setup_countdown_display(
     mut commands: Commands,
     bomb: Res<MatchBomb>,
     asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
 ) {
     commands.spawn((
         Node {
@@ -142,15 +165,14 @@ fn setup_countdown_display(
             ..default()
         },
         Text::from(bomb.turns_remaining().to_string()),
-        TextFont {
-            font_size: 24.0,
-            ..default()
-        },
+        theme.hud_text_style(),
         TextLayout {
             justify: JustifyText::Center,
             ..default()
         },
         CountdownDisplay,
+        HudDisplay,
+        StateScoped(ScreenState::Game),
     ));
 }
 
@@ -172,12 +194,9 @@ fn update_defused_count_display(
     bomb: Res<MatchBomb>,
 ) {
     if bomb.is_changed() {
-        let display_text = if bomb.defused_count == 1 {
-            format!("{} bomb defused", bomb.defused_count)
-        } else {
-            format!("{} bombs defused", bomb.defused_count)
-        };
-        commands.entity(*display).insert(Text::new(display_text));
+        commands
+            .entity(*display)
+            .insert(LocalizedText::new("hud.bombs_defused").count(bomb.defused_count));
     }
 }
 fn rotate_bomb(mut pieces_q: Query<&mut Transform, With<BombPiece>>, time: Res<Time>) {
@@ -201,6 +220,7 @@ fn on_defuse(mut commands: Commands, asset_server: Res<AssetServer>) {
         SceneRoot(mesh),
         Transform::from_translation(Vec3::new(7.0, 7.0, 6.0)),
         TextDespawn::default(),
+        StateScoped(ScreenState::Game),
     ));
 }
 
@@ -1,10 +1,14 @@
+use std::time::Duration;
+
 use bevy::{color::palettes::tailwind::RED_950, prelude::*};
 
 use crate::{
     audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings},
     game::{
+        attributes::{AttributeKind, AttributeRegistry, LockGlyph, LockMarker},
         bomb::BombPiece,
-        game_grid::{GameGrid, GamePiece, PopCellEvent},
+        game_grid::{GameGrid, GamePiece, PendingSpecials, PopCellEvent, SpecialPiece},
+        rules::GameRules,
     },
     screen::components::ScreenState,
 };
@@ -16,9 +20,12 @@ impl Plugin for CellLinePlugin {
         app.add_event::<CellVisitedEvent>()
             .add_event::<LineCompletedEvent>()
             .insert_resource(CellLine::new())
+            .insert_resource(ChainRule::default())
+            .insert_resource(CapturedArea::default())
             .add_systems(
                 Update,
-                (update_cell_visitation, draw_line).run_if(in_state(ScreenState::Game)),
+                (update_cell_visitation, draw_line, draw_captured_area)
+                    .run_if(in_state(ScreenState::Game)),
             )
             .add_systems(
                 Update,
@@ -33,26 +40,211 @@ pub struct CellVisitedEvent(pub Vec2);
 #[derive(Event)]
 pub struct LineCompletedEvent;
 
+/// Cells captured by the last closed loop, highlighted until the timer runs out
+#[derive(Resource, Debug)]
+pub struct CapturedArea {
+    pub cells: Vec<Vec2>,
+    pub timer: Timer,
+}
+
+impl Default for CapturedArea {
+    fn default() -> Self {
+        CapturedArea {
+            cells: vec![],
+            timer: Timer::new(Duration::from_secs_f32(1.5), TimerMode::Once),
+        }
+    }
+}
+
+/// How strictly a line has to keep matching as it grows
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChainRule {
+    /// Each step only has to share one attribute with the previous cell
+    #[default]
+    Loose,
+    /// The attribute shared on the first step has to hold for the whole line
+    Strict,
+}
+
+impl ChainRule {
+    pub fn score_multiplier(&self) -> i32 {
+        match self {
+            ChainRule::Loose => 1,
+            ChainRule::Strict => 2,
+        }
+    }
+
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            ChainRule::Loose => "rule.loose",
+            ChainRule::Strict => "rule.strict",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            ChainRule::Loose => ChainRule::Strict,
+            ChainRule::Strict => ChainRule::Loose,
+        }
+    }
+}
+
+/// Every attribute the two pieces have in common
+pub fn shared_attributes(a: &GamePiece, b: &GamePiece) -> Vec<AttributeKind> {
+    AttributeKind::ALL
+        .into_iter()
+        .filter(|kind| a.shares(*kind, b))
+        .collect()
+}
+
 #[derive(Resource, Clone, Debug)]
 pub struct CellLine {
     pub visited: Vec<Vec2>,
+    /// The rule this line was started under
+    pub rule: ChainRule,
+    /// Attributes that have held for every step of the line so far.
+    /// Empty until the first step is taken.
+    pub locked: Vec<AttributeKind>,
+    /// Set once the line has been sent off to score. It stays on the board while its pieces pop,
+    /// and can't be extended or sent again until the board is rebuilt
+    pub committed: bool,
 }
 
 impl CellLine {
     fn new() -> Self {
-        CellLine { visited: vec![] }
+        CellLine {
+            visited: vec![],
+            rule: ChainRule::default(),
+            locked: vec![],
+            committed: false,
+        }
     }
 
     fn visit(&mut self, pos: Vec2) {
         self.visited.push(pos);
     }
 
+    pub fn clear(&mut self) {
+        self.visited.clear();
+        self.locked.clear();
+        self.committed = false;
+    }
+
+    /// Sends the line off to score, unless it already has been
+    pub fn commit(&mut self, ew_line_complete: &mut EventWriter<LineCompletedEvent>) {
+        if !self.committed {
+            self.committed = true;
+            ew_line_complete.write(LineCompletedEvent);
+        }
+    }
+
+    /// Drops the last visited cell, working the lock out again from the cells that are left
+    pub fn step_back(&mut self, grid: &GameGrid) {
+        self.visited.pop();
+        self.locked.clear();
+        let visited = self.visited.clone();
+        for step in visited.windows(2) {
+            if let (Some(source), Some(target)) = (grid.get_piece(step[0]), grid.get_piece(step[1]))
+            {
+                self.lock(source, target);
+            }
+        }
+    }
+
+    /// Would stepping onto this position close the line back onto its start?
+    pub fn closes_loop(&self, position: Vec2) -> bool {
+        self.visited.len() >= 3 && self.visited.first() == Some(&position) && !self.is_closed()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.visited.len() > 3 && self.visited.first() == self.visited.last()
+    }
+
+    /// The cells making up the line, counting the start of a closed loop once
+    pub fn cells(&self) -> &[Vec2] {
+        if self.is_closed() {
+            &self.visited[..self.visited.len() - 1]
+        } else {
+            &self.visited
+        }
+    }
+
+    /// Every grid cell inside a closed loop, not counting the loop itself
+    pub fn enclosed_cells(&self, grid: &GameGrid) -> Vec<Vec2> {
+        if !self.is_closed() {
+            return vec![];
+        }
+
+        let polygon = self.cells();
+        let mut enclosed = vec![];
+        for (i, _) in grid.cells.indexed_iter() {
+            let position = Vec2::new(i.0 as f32, i.1 as f32);
+            if !polygon.contains(&position) && CellLine::point_in_polygon(position, polygon) {
+                enclosed.push(position);
+            }
+        }
+        enclosed
+    }
+
+    //Even-odd ray cast. Line vertices sit on grid cells and every edge joins neighbors,
+    //so no other cell can land exactly on an edge
+    fn point_in_polygon(point: Vec2, polygon: &[Vec2]) -> bool {
+        let mut inside = false;
+        let mut j = polygon.len() - 1;
+        for i in 0..polygon.len() {
+            let (a, b) = (polygon[i], polygon[j]);
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+            {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+
+    /// Narrows the locked attributes down to the ones shared by this step.
+    /// A step that shares none of them (e.g. a free step next to the bomb) leaves the lock alone.
+    fn lock(&mut self, source: &GamePiece, target: &GamePiece) {
+        let shared = shared_attributes(source, target);
+        if self.locked.is_empty() {
+            self.locked = shared;
+            return;
+        }
+
+        let narrowed: Vec<AttributeKind> = self
+            .locked
+            .iter()
+            .copied()
+            .filter(|attribute| shared.contains(attribute))
+            .collect();
+        if !narrowed.is_empty() {
+            self.locked = narrowed;
+        }
+    }
+
+    /// The attribute the line is locked to, once only one candidate is left
+    pub fn locked_attribute(&self) -> Option<AttributeKind> {
+        match self.locked.as_slice() {
+            [attribute] if self.rule == ChainRule::Strict => Some(*attribute),
+            _ => None,
+        }
+    }
+
     pub fn validate(&self, target: &GamePiece, source: &GamePiece, grid: &GameGrid) -> bool {
         let mut retval = true;
-        // Do they share a property?
-        if target.color != source.color
-            && target.shape != source.shape
-            && target.sound != source.sound
+        // Do they share a property? Wildcards share all of them
+        if shared_attributes(target, source).is_empty() {
+            retval = false
+        }
+
+        //Under the strict rule they have to share one of the locked properties
+        if self.rule == ChainRule::Strict
+            && !self.locked.is_empty()
+            && !self
+                .locked
+                .iter()
+                .any(|attribute| target.shares(*attribute, source))
         {
             retval = false
         }
@@ -62,8 +254,9 @@ impl CellLine {
             retval = false
         }
 
-        //Cant go back
-        if self.visited.contains(&grid.get_position(target)) {
+        //Cant go back, unless it closes the loop
+        let target_position = grid.get_position(target);
+        if self.visited.contains(&target_position) && !self.closes_loop(target_position) {
             retval = false
         }
         let mut segments = self.visited.clone();
@@ -120,7 +313,7 @@ impl CellLine {
 
         for i in 0..points.len().saturating_sub(1) {
             for j in i + 2..points.len().saturating_sub(1) {
-                if i == 0 && j == points.len() - 2 {
+                if i == 0 && j == points.len() - 2 && points.first() == points.last() {
                     continue; // skip closing segment if polyline is closed
                 }
                 let p1 = points[i];
@@ -135,6 +328,30 @@ impl CellLine {
         false
     }
 
+    /// Would visiting this piece extend the line, rather than ending it?
+    pub fn accepts(&self, target: &GamePiece, grid: &GameGrid, bomb: Vec2) -> bool {
+        let Some(Some(source)) = self.visited.last().map(|head| grid.get_piece(*head)) else {
+            return false;
+        };
+        if self.committed || self.is_closed() || source.id() == target.id() {
+            return false;
+        }
+        let diff_vec = (grid.get_position(target) - bomb).abs();
+        self.validate(target, source, grid) || diff_vec.x <= 1.0 && diff_vec.y <= 1.0
+    }
+
+    /// Can the line step from its head onto the bomb?
+    /// The bomb has to be next to the head, and can only be stepped on once
+    pub fn accepts_bomb(&self, bomb: Vec2) -> bool {
+        let Some(head) = self.visited.last() else {
+            return false;
+        };
+        !self.committed
+            && !self.is_closed()
+            && !self.visited.contains(&bomb)
+            && CellLine::is_neighbor(bomb, *head)
+    }
+
     fn is_neighbor(target: Vec2, source: Vec2) -> bool {
         let dx = (source.x - target.x).abs();
         let dy = (source.y - target.y).abs();
@@ -143,15 +360,21 @@ impl CellLine {
 }
 
 ///Check that target cell is valid
-fn update_cell_visitation(
+pub fn update_cell_visitation(
     mut er_visited: EventReader<CellVisitedEvent>,
     mut cell_line: ResMut<CellLine>,
     game_grid: Res<GameGrid>,
     mut ew_line_complete: EventWriter<LineCompletedEvent>,
     mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
     bomb: Single<&BombPiece>,
+    chain_rule: Res<ChainRule>,
 ) {
     for event in er_visited.read() {
+        //A closed loop can't be extended, nor can a line already sent off
+        if cell_line.committed || cell_line.is_closed() {
+            continue;
+        }
+
         if !cell_line.visited.is_empty() {
             if let Some(target) = game_grid.get_piece(event.0)
                 && let Some(source) = game_grid.get_piece(*cell_line.visited.last().unwrap())
@@ -164,28 +387,33 @@ fn update_cell_visitation(
                 if cell_line.validate(target, source, &game_grid)
                     || diff_vec.x <= 1.0 && diff_vec.y <= 1.0
                 {
-                    if !has_out(
-                        &game_grid,
-                        game_grid.get_position(target),
-                        cell_line.clone(),
-                        &bomb,
-                    ) {
-                        ew_line_complete.write(LineCompletedEvent);
+                    let closing = cell_line.closes_loop(event.0);
+                    cell_line.lock(source, target);
+                    if closing
+                        || !has_out(
+                            &game_grid,
+                            game_grid.get_position(target),
+                            cell_line.clone(),
+                            &bomb,
+                        )
+                    {
+                        cell_line.commit(&mut ew_line_complete);
                     }
                     ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
                         EffectName::ValidSelection,
                         Some(true),
                         None,
                     )));
-                    cell_line.visited.push(event.0);
+                    cell_line.visit(event.0);
                 } else {
-                    ew_line_complete.write(LineCompletedEvent);
+                    cell_line.commit(&mut ew_line_complete);
                 }
             }
 
         //Initialize the starting point
         } else {
-            cell_line.visited.push(event.0);
+            cell_line.rule = *chain_rule;
+            cell_line.visit(event.0);
             ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
                 EffectName::ValidSelection,
                 Some(true),
@@ -212,14 +440,19 @@ fn has_out(
 }
 
 ///Draws the line segments for visited cells
-fn draw_line(mut gizmos: Gizmos, cell_line: ResMut<CellLine>) {
+fn draw_line(mut gizmos: Gizmos, cell_line: ResMut<CellLine>, registry: Res<AttributeRegistry>) {
+    let lock = cell_line
+        .locked_attribute()
+        .map(|attribute| &registry.def(attribute).lock);
+    let start_color = lock.map_or(Color::srgba(5.0, 0.0, 5.0, 1.0), |lock| lock.color);
+
     let mut prev_point: Vec2 = Vec2::NEG_ONE;
     for point in cell_line.visited.iter() {
         if prev_point != Vec2::NEG_ONE {
             gizmos.line_gradient(
                 prev_point.extend(0.0) * 2.0,
                 point.extend(0.0) * 2.0,
-                Color::srgba(5.0, 0.0, 5.0, 1.0),
+                start_color,
                 Color::from(RED_950),
             );
             prev_point = *point;
@@ -227,16 +460,63 @@ fn draw_line(mut gizmos: Gizmos, cell_line: ResMut<CellLine>) {
             prev_point = *point;
         }
     }
+
+    if let Some(lock) = lock
+        && let Some(head) = cell_line.visited.last()
+    {
+        draw_lock_marker(&mut gizmos, lock, head.extend(0.0) * 2.0);
+    }
 }
 
-fn on_line_complete(
+///Marks the head of a strict line with the glyph of the locked attribute
+fn draw_lock_marker(gizmos: &mut Gizmos, lock: &LockMarker, position: Vec3) {
+    match &lock.glyph {
+        LockGlyph::Rings(radii) => {
+            for radius in radii {
+                gizmos.circle(position, *radius, lock.color);
+            }
+        }
+        LockGlyph::Square(side) => {
+            gizmos.rect(position, Vec2::splat(*side), lock.color);
+        }
+    }
+}
+
+///Highlights the cells captured by a closed loop, fading out as they pop
+fn draw_captured_area(mut gizmos: Gizmos, mut captured: ResMut<CapturedArea>, time: Res<Time>) {
+    if captured.cells.is_empty() {
+        return;
+    }
+
+    captured.timer.tick(time.delta());
+    if captured.timer.finished() {
+        captured.cells.clear();
+        return;
+    }
+
+    let alpha = 1.0 - captured.timer.fraction();
+    for cell in captured.cells.iter() {
+        gizmos.rect(
+            cell.extend(0.0) * 2.0,
+            Vec2::splat(1.8),
+            Color::srgba(5.0, 5.0, 0.5, alpha),
+        );
+    }
+}
+
+pub fn on_line_complete(
     mut cell_line: ResMut<CellLine>,
     mut game_grid: ResMut<GameGrid>,
+    mut pending_specials: ResMut<PendingSpecials>,
+    mut captured: ResMut<CapturedArea>,
+    registry: Res<AttributeRegistry>,
+    rules: Res<GameRules>,
+    bomb_q: Query<&BombPiece>,
     mut ew_pop_cell: EventWriter<PopCellEvent>,
     mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
 ) {
-    if cell_line.visited.len() <= 3 {
-        cell_line.visited.clear();
+    if !rules.is_scoring(cell_line.cells().len()) {
+        cell_line.clear();
         ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
             EffectName::Negative,
             Some(true),
@@ -244,14 +524,88 @@ fn on_line_complete(
         )));
         return;
     }
-    for cell in cell_line.visited.iter() {
+    let enclosed = cell_line.enclosed_cells(&game_grid);
+    let mut cleared = game_grid.special_targets(cell_line.cells());
+    cleared.retain(|cell| !enclosed.contains(cell));
+    cleared.extend(enclosed.iter());
+    for cell in cell_line.cells().iter().chain(cleared.iter()) {
+        let piece = *game_grid.get_piece(*cell);
         game_grid.pop_cell(*cell);
-        ew_pop_cell.write(PopCellEvent(*cell));
+        ew_pop_cell.write(PopCellEvent(*cell, piece));
+    }
+
+    if !enclosed.is_empty() {
+        captured.cells = enclosed;
+        captured.timer.reset();
+    }
+
+    //Long lines leave a special piece on the last piece they took, which may come before the bomb
+    if let Some(special) = SpecialPiece::from_line_length(cell_line.cells().len())
+        && let Some(end) = cell_line
+            .cells()
+            .iter()
+            .rev()
+            .find(|cell| !bomb_q.iter().any(|bomb| bomb.position() == **cell))
+    {
+        pending_specials.0.push((*end, special));
     }
 
     let mut new_grid_vec = vec![];
     for i in 0..game_grid.cells.cols() {
-        let mut new_col = GameGrid::collapse_column(game_grid.cells.clone(), i);
+        let mut new_col = GameGrid::collapse_column(game_grid.cells.clone(), i, &registry);
         new_grid_vec.append(&mut new_col);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_through(cells: &[(f32, f32)]) -> CellLine {
+        let mut line = CellLine::new();
+        for (x, y) in cells {
+            line.visit(Vec2::new(*x, *y));
+        }
+        line
+    }
+
+    const RING: [(f32, f32); 8] = [
+        (1.0, 1.0),
+        (1.0, 2.0),
+        (1.0, 3.0),
+        (2.0, 3.0),
+        (3.0, 3.0),
+        (3.0, 2.0),
+        (3.0, 1.0),
+        (2.0, 1.0),
+    ];
+
+    #[test]
+    fn square_loop_encloses_its_middle_cell() {
+        let mut cells = RING.to_vec();
+        cells.push(RING[0]);
+        let line = line_through(&cells);
+
+        assert!(line.is_closed());
+        assert_eq!(
+            line.enclosed_cells(&GameGrid::default()),
+            vec![Vec2::new(2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn tight_loop_encloses_nothing() {
+        let line = line_through(&[(1.0, 1.0), (1.0, 2.0), (2.0, 2.0), (2.0, 1.0), (1.0, 1.0)]);
+
+        assert!(line.is_closed());
+        assert!(line.enclosed_cells(&GameGrid::default()).is_empty());
+    }
+
+    #[test]
+    fn open_line_encloses_nothing() {
+        let line = line_through(&RING);
+
+        assert!(!line.is_closed());
+        assert!(line.enclosed_cells(&GameGrid::default()).is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::{Action, ActionInput},
+    audio_server::components::{EffectName, PlayNoteEvent, PlaySoundEffectEvent, SfxSettings},
+    game::{
+        GameState,
+        attributes::AttributeRegistry,
+        bomb::BombPiece,
+        cell_line::{CellLine, CellVisitedEvent, shared_attributes},
+        game_grid::{GRID_HEIGHT, GRID_WIDTH, GameGrid},
+        input_handler::play_piece_note,
+        pause::PauseState,
+    },
+    screen::{components::ScreenState, focus::StickRepeat},
+};
+
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GridCursor::default())
+            .add_systems(OnEnter(ScreenState::Game), reset_cursor)
+            .add_systems(
+                Update,
+                (move_cursor, confirm_cursor, step_back, show_hint)
+                    .chain()
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(PauseState::Running))
+                    .run_if(not(in_state(GameState::GameOver))),
+            )
+            .add_systems(Update, draw_cursor.run_if(in_state(ScreenState::Game)));
+    }
+}
+
+/// The board cell picked by keyboard or gamepad input
+#[derive(Resource, Debug)]
+pub struct GridCursor {
+    pub position: IVec2,
+    /// Hidden until a cursor key is used, so mouse players don't see it
+    pub visible: bool,
+}
+
+impl Default for GridCursor {
+    fn default() -> Self {
+        GridCursor {
+            position: IVec2::new(GRID_HEIGHT as i32 / 2, GRID_WIDTH as i32 / 2),
+            visible: false,
+        }
+    }
+}
+
+impl GridCursor {
+    pub fn cell(&self) -> Vec2 {
+        self.position.as_vec2()
+    }
+
+    /// Moves the cursor by a step, stopping at the edge of the board
+    pub fn step(&mut self, direction: IVec2) {
+        self.position = (self.position + direction).clamp(
+            IVec2::ZERO,
+            IVec2::new(GRID_HEIGHT as i32 - 1, GRID_WIDTH as i32 - 1),
+        );
+        self.visible = true;
+    }
+}
+
+//Systems
+
+fn reset_cursor(mut cursor: ResMut<GridCursor>) {
+    *cursor = GridCursor::default();
+}
+
+///Moves the cursor and plays the note of the piece it lands on, like hovering with the mouse
+fn move_cursor(
+    actions: ActionInput,
+    gamepads: Query<&Gamepad>,
+    mut repeat: Local<StickRepeat>,
+    time: Res<Time>,
+    mut cursor: ResMut<GridCursor>,
+    grid: Res<GameGrid>,
+    bomb: Single<&BombPiece>,
+    registry: Res<AttributeRegistry>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    mut ew_note_player: EventWriter<PlayNoteEvent>,
+) {
+    let stick: Vec2 = gamepads.iter().map(|gamepad| gamepad.left_stick()).sum();
+    let direction = actions.cursor_step() + repeat.update(stick, time.delta());
+    if direction == IVec2::ZERO {
+        return;
+    }
+
+    cursor.step(direction.clamp(IVec2::NEG_ONE, IVec2::ONE));
+    if cursor.cell() == bomb.position() {
+        ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+            EffectName::Fuse,
+            Some(true),
+            None,
+        )));
+    } else if let Some(piece) = grid.get_piece(cursor.cell()) {
+        play_piece_note(piece, &registry, &mut ew_note_player);
+    }
+}
+
+///Starts or extends the line from the cursor, the same way clicking a piece does
+fn confirm_cursor(
+    actions: ActionInput,
+    mut cursor: ResMut<GridCursor>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut cell_line: ResMut<CellLine>,
+    bomb: Single<&BombPiece>,
+    mut ew_visited: EventWriter<CellVisitedEvent>,
+) {
+    if !actions.just_pressed(Action::Confirm) {
+        return;
+    }
+    cursor.visible = true;
+
+    //The bomb is stepped onto directly, as in on_bomb_click
+    if cursor.cell() == bomb.position() {
+        if cell_line.accepts_bomb(bomb.position()) {
+            cell_line.visited.push(bomb.position());
+        }
+        return;
+    }
+
+    if state.get() == &GameState::FreePick {
+        next_state.set(GameState::PickNext);
+    }
+    ew_visited.write(CellVisitedEvent(cursor.cell()));
+}
+
+///Undoes the last cell, cancelling the line once it is empty
+fn step_back(
+    actions: ActionInput,
+    mut cell_line: ResMut<CellLine>,
+    mut cursor: ResMut<GridCursor>,
+    grid: Res<GameGrid>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !actions.just_pressed(Action::StepBack)
+        || cell_line.visited.is_empty()
+        || cell_line.committed
+    {
+        return;
+    }
+
+    cell_line.step_back(&grid);
+    match cell_line.visited.last() {
+        Some(head) => {
+            cursor.position = head.as_ivec2();
+            cursor.visible = true;
+        }
+        None => next_state.set(GameState::FreePick),
+    }
+}
+
+///Moves the cursor to a cell worth visiting next: one that extends the line,
+///or with no line yet, the nearest piece that matches one of its neighbors
+fn show_hint(
+    actions: ActionInput,
+    mut cursor: ResMut<GridCursor>,
+    cell_line: Res<CellLine>,
+    grid: Res<GameGrid>,
+    bomb: Single<&BombPiece>,
+    registry: Res<AttributeRegistry>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    mut ew_note_player: EventWriter<PlayNoteEvent>,
+) {
+    if !actions.just_pressed(Action::Hint) {
+        return;
+    }
+
+    let suggestion = match cell_line.visited.last() {
+        Some(head) => neighbors(head.as_ivec2()).find(|cell| {
+            grid.get_piece(cell.as_vec2())
+                .as_ref()
+                .is_some_and(|piece| cell_line.accepts(piece, &grid, bomb.position()))
+        }),
+        None => (0..GRID_HEIGHT as i32)
+            .flat_map(|x| (0..GRID_WIDTH as i32).map(move |y| IVec2::new(x, y)))
+            .filter(|cell| {
+                let Some(piece) = grid.get_piece(cell.as_vec2()) else {
+                    return false;
+                };
+                neighbors(*cell).any(|other| {
+                    grid.get_piece(other.as_vec2())
+                        .as_ref()
+                        .is_some_and(|other| !shared_attributes(piece, other).is_empty())
+                })
+            })
+            .min_by_key(|cell| (*cell - cursor.position).length_squared()),
+    };
+
+    match suggestion.and_then(|cell| {
+        grid.get_piece(cell.as_vec2())
+            .as_ref()
+            .map(|piece| (cell, piece))
+    }) {
+        Some((cell, piece)) => {
+            cursor.position = cell;
+            cursor.visible = true;
+            play_piece_note(piece, &registry, &mut ew_note_player);
+        }
+        None => {
+            ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+                EffectName::Negative,
+                Some(true),
+                None,
+            )));
+        }
+    }
+}
+
+//The cells around a position that are on the board
+fn neighbors(position: IVec2) -> impl Iterator<Item = IVec2> {
+    (-1..=1)
+        .flat_map(|x| (-1..=1).map(move |y| IVec2::new(x, y)))
+        .filter(|offset| *offset != IVec2::ZERO)
+        .map(move |offset| position + offset)
+        .filter(|cell| {
+            cell.cmpge(IVec2::ZERO).all()
+                && cell.x < GRID_HEIGHT as i32
+                && cell.y < GRID_WIDTH as i32
+        })
+}
+
+fn draw_cursor(mut gizmos: Gizmos, cursor: Res<GridCursor>, time: Res<Time>) {
+    if !cursor.visible {
+        return;
+    }
+
+    let position = cursor.cell().extend(0.0) * 2.0;
+    let pulse = 0.5 + 0.5 * (time.elapsed_secs() * 3.0).sin();
+    gizmos.rect(position, Vec2::splat(2.0), Color::srgba(5.0, 5.0, 5.0, 1.0));
+    gizmos.rect(
+        position,
+        Vec2::splat(1.6 + 0.2 * pulse),
+        Color::srgba(5.0, 5.0, 5.0, 0.5),
+    );
+}
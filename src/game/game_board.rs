@@ -3,9 +3,12 @@ use std::f32::consts::TAU;
 use std::time::Duration;
 
 use crate::audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings};
+use crate::game::attributes::{AttributeKind, AttributeRegistry, AttributeRender, PieceAttribute};
 use crate::game::bomb::BombPiece;
 use crate::game::cell_line::CellLine;
 use crate::game::input_handler::{on_bomb_click, on_over};
+use crate::game::palette::PaletteSettings;
+use crate::game::pause::PauseState;
 use crate::{game::game_grid::*, screen::components::ScreenState};
 use bevy::prelude::*;
 use bevy::render::view::RenderLayers;
@@ -17,6 +20,7 @@ pub struct GameBoardPlugin;
 impl Plugin for GameBoardPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(DespawnQueue(vec![]))
+            .init_resource::<MaterialMap>()
             .insert_resource(DespawnTimer(Timer::new(
                 Duration::from_secs_f32(0.25),
                 TimerMode::Repeating,
@@ -28,10 +32,12 @@ impl Plugin for GameBoardPlugin {
             )
             .add_systems(Update, update_board.run_if(on_event::<UpdateBoardEvent>))
             .add_systems(Update, on_pop_cell.run_if(on_event::<PopCellEvent>))
-            .add_systems(Startup, (load_model_map, load_material_map))
+            .add_systems(Startup, load_model_map)
             .add_systems(
                 Update,
-                (rotate_pieces, pop_cell).run_if(in_state(ScreenState::Game)),
+                (rotate_pieces, pop_cell)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(PauseState::Running)),
             )
             .add_observer(apply_material);
     }
@@ -41,10 +47,41 @@ impl Plugin for GameBoardPlugin {
 pub struct UpdateBoardEvent;
 
 #[derive(Resource)]
-pub struct ModelMap(HashMap<PieceShape, Handle<Scene>>);
+pub struct ModelMap {
+    shapes: HashMap<PieceShape, Handle<Scene>>,
+    specials: HashMap<SpecialPiece, SpecialVfx>,
+}
 
-#[derive(Resource)]
-pub struct MaterialMap(HashMap<PieceColor, Handle<StandardMaterial>>);
+impl ModelMap {
+    pub fn shape(&self, shape: &PieceShape) -> &Handle<Scene> {
+        self.shapes.get(shape).unwrap()
+    }
+
+    pub fn special(&self, special: &SpecialPiece) -> &SpecialVfx {
+        self.specials.get(special).unwrap()
+    }
+}
+
+/// Meshes layered over a piece to mark it as special
+pub struct SpecialVfx {
+    pub parts: Vec<(Handle<Mesh>, Transform)>,
+    pub material: Handle<StandardMaterial>,
+}
+
+//Marker for special piece effects, so they keep their own material
+#[derive(Component)]
+pub struct SpecialPieceVfx;
+
+/// Piece materials, each a model's own material painted in one color.
+/// Meshes with the same source material and color share one, so the palette only repaints these
+#[derive(Resource, Default)]
+pub struct MaterialMap(HashMap<(AssetId<StandardMaterial>, PieceColor), Handle<StandardMaterial>>);
+
+impl MaterialMap {
+    pub fn iter(&self) -> impl Iterator<Item = (&PieceColor, &Handle<StandardMaterial>)> {
+        self.0.iter().map(|((_, color), handle)| (color, handle))
+    }
+}
 
 //Marker for the parent to which pieces are added as children
 #[derive(Component)]
@@ -78,79 +115,107 @@ fn setup_despawn_timer(mut despawn_timer: ResMut<DespawnTimer>) {
     );
     despawn_timer.0 = timer;
 }
-fn load_model_map(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let map: HashMap<PieceShape, Handle<Scene>> = HashMap::from([
-        (
-            PieceShape::Circle,
-            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/circle.glb")),
-        ),
-        (
-            PieceShape::Square,
-            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/cuboid.glb")),
-        ),
-        (
-            PieceShape::Triangle,
-            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/pyramid.glb")),
-        ),
-        (
-            PieceShape::X,
-            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/x.glb")),
-        ),
+fn load_model_map(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    registry: Res<AttributeRegistry>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut map: HashMap<PieceShape, Handle<Scene>> = registry
+        .def(AttributeKind::Shape)
+        .values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, value)| match value.render {
+            AttributeRender::Model(path) => Some((
+                PieceShape::from_index(i),
+                asset_server.load(GltfAssetLabel::Scene(0).from_asset(path)),
+            )),
+            _ => None,
+        })
+        .collect();
+    map.insert(
+        PieceShape::Bomb,
+        asset_server.load(GltfAssetLabel::Scene(0).from_asset(PieceShape::Bomb.model_path())),
+    );
+
+    let glow = |color: Color| StandardMaterial {
+        base_color: color.with_alpha(0.6),
+        emissive: color.to_linear() * 4.0,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    };
+
+    let beam = meshes.add(Cuboid::new(2.2, 0.15, 0.15));
+    let specials: HashMap<SpecialPiece, SpecialVfx> = HashMap::from([
         (
-            PieceShape::Plus,
-            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/plus.glb")),
+            SpecialPiece::Wildcard,
+            SpecialVfx {
+                parts: vec![
+                    (meshes.add(Torus::new(0.7, 0.85)), Transform::default()),
+                    (
+                        meshes.add(Torus::new(0.7, 0.85)),
+                        Transform::from_rotation(Quat::from_rotation_x(TAU / 4.0)),
+                    ),
+                ],
+                material: materials.add(glow(Color::WHITE)),
+            },
         ),
         (
-            PieceShape::Diamond,
-            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/diamond.glb")),
+            SpecialPiece::LineBlaster,
+            SpecialVfx {
+                parts: vec![
+                    (beam.clone(), Transform::default()),
+                    (
+                        beam,
+                        Transform::from_rotation(Quat::from_rotation_z(TAU / 4.0)),
+                    ),
+                ],
+                material: materials.add(glow(Color::srgb(1.0, 0.5, 0.0))),
+            },
         ),
         (
-            PieceShape::Bomb,
-            asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/bomb.glb")),
+            SpecialPiece::ColorBomb,
+            SpecialVfx {
+                parts: vec![(meshes.add(Sphere::new(1.0)), Transform::default())],
+                material: materials.add(glow(Color::srgb(0.6, 0.2, 1.0))),
+            },
         ),
     ]);
 
-    commands.insert_resource(ModelMap(map));
+    commands.insert_resource(ModelMap {
+        shapes: map,
+        specials,
+    });
 }
 
-fn load_material_map(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
-    let mat_colors = [
-        PieceColor::Pink,
-        PieceColor::Green,
-        PieceColor::Blue,
-        PieceColor::Yellow,
-        PieceColor::Orange,
-        PieceColor::Purple,
-        PieceColor::Cyan,
-        PieceColor::Red,
-    ];
-
-    let mat_map: HashMap<PieceColor, Handle<StandardMaterial>> = mat_colors
-        .into_iter()
-        .map(|color| (color, materials.add(color.to_color())))
-        .collect();
-
-    commands.insert_resource(MaterialMap(mat_map));
-}
-
-fn setup_board_system(
+pub fn setup_board_system(
     mut commands: Commands,
     gg: Res<GameGrid>,
     model_map: ResMut<ModelMap>,
     asset_server: Res<AssetServer>,
 ) {
     let container = commands
-        .spawn((BoardContainer, Transform::from_translation(Vec3::ZERO)))
+        .spawn((
+            BoardContainer,
+            Transform::from_translation(Vec3::ZERO),
+            StateScoped(ScreenState::Game),
+        ))
         .id();
 
-    let mut bomb_pos: (usize, usize) = (0, 0);
-    let mut rng = rand::rng();
-    bomb_pos.0 = rng.random_range(0..gg.cells.rows());
-    bomb_pos.1 = rng.random_range(0..gg.cells.cols());
+    let bomb_pos = gg.bomb.unwrap_or_else(|| {
+        let mut rng = rand::rng();
+        (
+            rng.random_range(0..gg.cells.rows()),
+            rng.random_range(0..gg.cells.cols()),
+        )
+    });
 
     for (i, cell) in gg.cells.indexed_iter() {
         if i == bomb_pos {
-            let model = model_map.0.get(&PieceShape::Bomb).unwrap();
+            let model = model_map.shape(&PieceShape::Bomb);
 
             let child = commands
                 .spawn((
@@ -163,50 +228,48 @@ fn setup_board_system(
                 .id();
             commands.entity(container).add_child(child);
         } else if let Some(piece) = cell {
-            let model = match piece.shape {
-                PieceShape::Circle => model_map.0.get(&PieceShape::Circle).unwrap(),
-                PieceShape::Square => model_map.0.get(&PieceShape::Square).unwrap(),
-                PieceShape::Triangle => model_map.0.get(&PieceShape::Triangle).unwrap(),
-                PieceShape::X => model_map.0.get(&PieceShape::X).unwrap(),
-                PieceShape::Plus => model_map.0.get(&PieceShape::Plus).unwrap(),
-                PieceShape::Diamond => model_map.0.get(&PieceShape::Diamond).unwrap(),
-                PieceShape::Bomb => model_map.0.get(&PieceShape::Bomb).unwrap(),
-            };
+            let model = model_map.shape(&piece.shape());
 
             let child = commands
                 .spawn((
                     BoardPiece::new(*piece),
                     Transform::from_translation(Vec3::new(i.0 as f32 * 2.0, i.1 as f32 * 2.0, 0.0)),
                     SceneRoot(model.clone()),
-                    piece.color,
+                    piece.color(),
                     RenderLayers::layer(0),
                 ))
                 .observe(super::input_handler::on_click)
                 .observe(super::input_handler::on_over)
                 .id();
 
+            if let Some(special) = piece.special {
+                spawn_special_vfx(&mut commands, child, model_map.special(&special));
+            }
             commands.entity(container).add_child(child);
         }
     }
 }
 
-fn update_board(
+pub fn update_board(
     mut commands: Commands,
     container: Single<Entity, With<BoardContainer>>,
     model_map: ResMut<ModelMap>,
     gg: Res<GameGrid>,
     mut cell_line: ResMut<CellLine>,
 ) {
-    cell_line.visited.clear();
+    cell_line.clear();
     commands.entity(*container).despawn_related::<Children>();
-    let mut bomb_pos: (usize, usize) = (0, 0);
-    let mut rng = rand::rng();
-    bomb_pos.0 = rng.random_range(0..gg.cells.rows());
-    bomb_pos.1 = rng.random_range(0..gg.cells.cols());
+    let bomb_pos = gg.bomb.unwrap_or_else(|| {
+        let mut rng = rand::rng();
+        (
+            rng.random_range(0..gg.cells.rows()),
+            rng.random_range(0..gg.cells.cols()),
+        )
+    });
 
     for (i, cell) in gg.cells.indexed_iter() {
         if i == bomb_pos {
-            let model = model_map.0.get(&PieceShape::Bomb).unwrap();
+            let model = model_map.shape(&PieceShape::Bomb);
             let child = commands
                 .spawn((
                     BombPiece(bomb_pos),
@@ -219,59 +282,82 @@ fn update_board(
 
             commands.entity(*container).add_child(child);
         } else if let Some(piece) = cell {
-            let model = match piece.shape {
-                PieceShape::Circle => model_map.0.get(&PieceShape::Circle).unwrap(),
-                PieceShape::Square => model_map.0.get(&PieceShape::Square).unwrap(),
-                PieceShape::Triangle => model_map.0.get(&PieceShape::Triangle).unwrap(),
-                PieceShape::X => model_map.0.get(&PieceShape::X).unwrap(),
-                PieceShape::Plus => model_map.0.get(&PieceShape::Plus).unwrap(),
-                PieceShape::Diamond => model_map.0.get(&PieceShape::Diamond).unwrap(),
-                PieceShape::Bomb => model_map.0.get(&PieceShape::Bomb).unwrap(),
-            };
+            let model = model_map.shape(&piece.shape());
 
             let child = commands
                 .spawn((
                     BoardPiece::new(*piece),
                     Transform::from_translation(Vec3::new(i.0 as f32 * 2.0, i.1 as f32 * 2.0, 0.0)),
                     SceneRoot(model.clone()),
-                    piece.color,
+                    piece.color(),
                 ))
                 .observe(super::input_handler::on_over)
                 .observe(super::input_handler::on_click)
                 .id();
 
+            if let Some(special) = piece.special {
+                spawn_special_vfx(&mut commands, child, model_map.special(&special));
+            }
             commands.entity(*container).add_child(child);
         }
     }
 }
+fn spawn_special_vfx(commands: &mut Commands, piece: Entity, vfx: &SpecialVfx) {
+    for (mesh, transform) in vfx.parts.iter() {
+        let part = commands
+            .spawn((
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(vfx.material.clone()),
+                *transform,
+                SpecialPieceVfx,
+            ))
+            .id();
+        commands.entity(piece).add_child(part);
+    }
+}
+
+///Paints every mesh of a piece in its color, keeping the rest of the model's own material
 fn apply_material(
     trigger: Trigger<SceneInstanceReady>,
     mut commands: Commands,
     children: Query<&Children>,
     mesh_materials: Query<&MeshMaterial3d<StandardMaterial>>,
-    material_map: Res<MaterialMap>,
+    mut material_map: ResMut<MaterialMap>,
     mut asset_materials: ResMut<Assets<StandardMaterial>>,
     piece: Query<&BoardPiece>,
+    vfx_q: Query<(), With<SpecialPieceVfx>>,
+    registry: Res<AttributeRegistry>,
+    settings: Res<PaletteSettings>,
     asset_server: Res<AssetServer>,
 ) {
+    let Ok(piece) = piece.get(trigger.target()) else {
+        return;
+    };
+    let color = piece.game_piece.color();
     for descendents in children.iter_descendants(trigger.target()) {
-        let Ok(piece) = piece.get(trigger.target()) else {
-            return;
-        };
-        if let Some(material) = mesh_materials
-            .get(descendents)
-            .ok()
-            .and_then(|id| asset_materials.get_mut(id.id()))
-        {
-            let mut new_material = material.clone();
-            new_material.base_color = piece.game_piece.color.to_color();
-            new_material.base_color_texture = Some(asset_server.load("image/purple_concrete.png"));
-            new_material.emissive = piece.game_piece.color.to_color().to_linear();
-
-            commands
-                .entity(descendents)
-                .insert(MeshMaterial3d(asset_materials.add(new_material)));
+        if vfx_q.contains(descendents) {
+            continue;
         }
+        let Ok(source) = mesh_materials.get(descendents) else {
+            continue;
+        };
+        let material = match material_map.0.get(&(source.id(), color)) {
+            Some(material) => material.clone(),
+            None => {
+                let Some(mut painted) = asset_materials.get(source.id()).cloned() else {
+                    continue;
+                };
+                settings.paint(&mut painted, color, &registry, &asset_server);
+                let material = asset_materials.add(painted);
+                material_map
+                    .0
+                    .insert((source.id(), color), material.clone());
+                material
+            }
+        };
+        commands
+            .entity(descendents)
+            .insert(MeshMaterial3d(material));
     }
 }
 
@@ -307,6 +393,8 @@ fn pop_cell(
     mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
     mut ew_update_board: EventWriter<UpdateBoardEvent>,
     mut grid: ResMut<GameGrid>,
+    mut pending_specials: ResMut<PendingSpecials>,
+    registry: Res<AttributeRegistry>,
 ) {
     timer.0.tick(time.delta());
 
@@ -324,7 +412,11 @@ fn pop_cell(
         )));
         despawn_queue.0.remove(0);
         if despawn_queue.0.is_empty() {
-            let new_grid = GameGrid::new();
+            let mut new_grid = GameGrid::new(&registry);
+            for (position, special) in pending_specials.0.drain(..) {
+                new_grid.place_special(position, special);
+            }
+            new_grid.place_bomb(&mut rand::rng());
             *grid = new_grid;
             ew_update_board.write_default();
         }
@@ -1,12 +1,16 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use uuid::Uuid;
 
 use bevy::prelude::*;
 use grid::*;
 
-use rand::distr::{Distribution, StandardUniform};
-use rand::prelude::*;
-
-use crate::game::cell_line::CellLine;
+use crate::{
+    audio_server::synth::{Accidental, Pitch},
+    game::{
+        attributes::{AttributeKind, AttributeRegistry, PieceAttribute},
+        cell_line::CellLine,
+    },
+};
 
 pub struct GameGridPlugin;
 
@@ -15,9 +19,14 @@ pub const GRID_HEIGHT: usize = 9;
 
 impl Plugin for GameGridPlugin {
     fn build(&self, app: &mut App) {
+        let registry = AttributeRegistry::default();
+        let seed = BoardSeed::random();
         app.add_event::<InitializeGridEvent>()
             .add_event::<PopCellEvent>()
-            .insert_resource(GameGrid::new());
+            .insert_resource(PendingSpecials::default())
+            .insert_resource(GameGrid::seeded(&registry, seed.0))
+            .insert_resource(seed)
+            .insert_resource(registry);
     }
 }
 
@@ -25,8 +34,9 @@ impl Plugin for GameGridPlugin {
 #[derive(Event, Debug, Default)]
 pub struct InitializeGridEvent;
 
+/// Fired for every cell popped, along with the piece that was in it
 #[derive(Event, Debug, Default)]
-pub struct PopCellEvent(pub Vec2);
+pub struct PopCellEvent(pub Vec2, pub Option<GamePiece>);
 
 /// This is a list of available colors for pieces
 
@@ -55,20 +65,45 @@ impl PieceColor {
             PieceColor::Red => Color::srgb(1.0, 0.06, 0.24),  // #FF103C
         }
     }
+
+    /// The pattern drawn over this color when patterns are turned on
+    pub fn pattern_path(&self) -> &'static str {
+        match self {
+            PieceColor::Pink => "image/patterns/dots.png",
+            PieceColor::Green => "image/patterns/horizontal_stripes.png",
+            PieceColor::Blue => "image/patterns/vertical_stripes.png",
+            PieceColor::Yellow => "image/patterns/checker.png",
+            PieceColor::Orange => "image/patterns/diagonal_stripes.png",
+            PieceColor::Purple => "image/patterns/crosshatch.png",
+            PieceColor::Cyan => "image/patterns/rings.png",
+            PieceColor::Red => "image/patterns/grid.png",
+        }
+    }
 }
 
-impl Distribution<PieceColor> for StandardUniform {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> PieceColor {
-        match rng.random_range(0..=7) {
-            0 => PieceColor::Pink,
-            1 => PieceColor::Green,
-            2 => PieceColor::Blue,
-            3 => PieceColor::Yellow,
-            4 => PieceColor::Orange,
-            5 => PieceColor::Purple,
-            6 => PieceColor::Cyan,
-            7 => PieceColor::Red,
-            _ => PieceColor::Red,
+impl PieceAttribute for PieceColor {
+    const KIND: AttributeKind = AttributeKind::Color;
+    const VALUES: &'static [Self] = &[
+        PieceColor::Pink,
+        PieceColor::Green,
+        PieceColor::Blue,
+        PieceColor::Yellow,
+        PieceColor::Orange,
+        PieceColor::Purple,
+        PieceColor::Cyan,
+        PieceColor::Red,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            PieceColor::Pink => "Pink",
+            PieceColor::Green => "Green",
+            PieceColor::Blue => "Blue",
+            PieceColor::Yellow => "Yellow",
+            PieceColor::Orange => "Orange",
+            PieceColor::Purple => "Purple",
+            PieceColor::Cyan => "Cyan",
+            PieceColor::Red => "Red",
         }
     }
 }
@@ -85,16 +120,41 @@ pub enum PieceShape {
     Bomb,
 }
 
-impl Distribution<PieceShape> for StandardUniform {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> PieceShape {
-        match rng.random_range(0..=5) {
-            0 => PieceShape::Circle,
-            1 => PieceShape::Square,
-            2 => PieceShape::Triangle,
-            3 => PieceShape::X,
-            4 => PieceShape::Plus,
-            5 => PieceShape::Diamond,
-            _ => PieceShape::Diamond,
+impl PieceShape {
+    pub fn model_path(&self) -> &'static str {
+        match self {
+            PieceShape::Circle => "models/circle.glb",
+            PieceShape::Square => "models/cuboid.glb",
+            PieceShape::Triangle => "models/pyramid.glb",
+            PieceShape::X => "models/x.glb",
+            PieceShape::Plus => "models/plus.glb",
+            PieceShape::Diamond => "models/diamond.glb",
+            PieceShape::Bomb => "models/bomb.glb",
+        }
+    }
+}
+
+// The bomb is placed by the board, never dealt
+impl PieceAttribute for PieceShape {
+    const KIND: AttributeKind = AttributeKind::Shape;
+    const VALUES: &'static [Self] = &[
+        PieceShape::Circle,
+        PieceShape::Square,
+        PieceShape::Triangle,
+        PieceShape::X,
+        PieceShape::Plus,
+        PieceShape::Diamond,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            PieceShape::Circle => "Circle",
+            PieceShape::Square => "Square",
+            PieceShape::Triangle => "Triangle",
+            PieceShape::X => "X",
+            PieceShape::Plus => "Plus",
+            PieceShape::Diamond => "Diamond",
+            PieceShape::Bomb => "Bomb",
         }
     }
 }
@@ -111,82 +171,169 @@ pub enum PieceSound {
     G,
 }
 
-impl Distribution<PieceSound> for StandardUniform {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> PieceSound {
-        match rng.random_range(0..=5) {
-            0 => PieceSound::A,
-            1 => PieceSound::B,
-            2 => PieceSound::C,
-            3 => PieceSound::D,
-            4 => PieceSound::E,
-            5 => PieceSound::F,
-            _ => PieceSound::G,
+impl PieceSound {
+    /// The note in the written octave. The instrument and octave it plays in are settings
+    pub fn pitch(&self) -> Pitch {
+        let semitone = match self {
+            PieceSound::C => 0,
+            PieceSound::D => 2,
+            PieceSound::E => 4,
+            PieceSound::F => 5,
+            PieceSound::G => 7,
+            PieceSound::A => 9,
+            PieceSound::B => 11,
+        };
+        Pitch::new(semitone, Accidental::Natural, Pitch::MIDDLE_OCTAVE)
+    }
+}
+
+impl PieceAttribute for PieceSound {
+    const KIND: AttributeKind = AttributeKind::Sound;
+    const VALUES: &'static [Self] = &[
+        PieceSound::A,
+        PieceSound::B,
+        PieceSound::C,
+        PieceSound::D,
+        PieceSound::E,
+        PieceSound::F,
+        PieceSound::G,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            PieceSound::A => "A",
+            PieceSound::B => "B",
+            PieceSound::C => "C",
+            PieceSound::D => "D",
+            PieceSound::E => "E",
+            PieceSound::F => "F",
+            PieceSound::G => "G",
+        }
+    }
+}
+
+// Special pieces are left behind by long lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpecialPiece {
+    /// Matches any attribute
+    Wildcard,
+    /// Clears its row and column when included in a line
+    LineBlaster,
+    /// Pops every piece sharing its color when included in a line
+    ColorBomb,
+}
+
+impl SpecialPiece {
+    /// Shortest line that leaves a special piece behind
+    pub const MIN_LINE_LENGTH: usize = 7;
+
+    /// The special piece a completed line of this length leaves at its end
+    pub fn from_line_length(length: usize) -> Option<Self> {
+        match length {
+            0..7 => None,
+            7..9 => Some(SpecialPiece::Wildcard),
+            9..11 => Some(SpecialPiece::LineBlaster),
+            _ => Some(SpecialPiece::ColorBomb),
         }
     }
 }
 
+/// Seed the current run's starting board was dealt from
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct BoardSeed(pub u64);
+
+impl BoardSeed {
+    pub fn random() -> Self {
+        BoardSeed(rand::random())
+    }
+}
+
+/// Special pieces waiting to be placed once the board is regenerated
+#[derive(Resource, Debug, Default)]
+pub struct PendingSpecials(pub Vec<(Vec2, SpecialPiece)>);
+
 ///A game piece containing its iproperties
 #[derive(Debug, Clone, Copy)]
 pub struct GamePiece {
-    pub color: PieceColor,
-    pub shape: PieceShape,
-    pub sound: PieceSound,
+    // Value index of every attribute, slotted by AttributeKind::index
+    attributes: [usize; AttributeKind::COUNT],
+    pub special: Option<SpecialPiece>,
     id: Uuid,
 }
 
 impl GamePiece {
     pub fn new(color: PieceColor, shape: PieceShape, sound: PieceSound) -> Self {
-        GamePiece {
-            color,
-            shape,
-            sound,
+        let mut piece = GamePiece {
+            attributes: [0; AttributeKind::COUNT],
+            special: None,
             id: Uuid::new_v4(),
-        }
+        };
+        piece.set(color).set(shape).set(sound);
+        piece
     }
 
     pub fn id(&self) -> Uuid {
         self.id
     }
 
-    fn random() -> Self {
-        let mut rng = rand::rng();
-        GamePiece {
-            color: rng.random(),
-            shape: rng.random(),
-            sound: rng.random(),
-            id: Uuid::new_v4(),
-        }
+    pub fn get<T: PieceAttribute>(&self) -> T {
+        T::from_index(self.attributes[T::KIND.index()])
     }
 
-    fn reroll(&mut self) {
-        let mut rng = rand::rng();
-        self.color = rng.random();
-        self.shape = rng.random();
+    pub fn set<T: PieceAttribute>(&mut self, value: T) -> &mut Self {
+        self.attributes[T::KIND.index()] = value.index();
+        self
     }
 
-    fn compare(&self, other: Option<GamePiece>) -> usize {
-        let mut similarity = 0;
-        if let Some(op) = other {
-            if self.shape == op.shape {
-                similarity += 1;
-            }
+    /// Index of this piece's value for an attribute, as listed in the registry
+    pub fn value_index(&self, kind: AttributeKind) -> usize {
+        self.attributes[kind.index()]
+    }
 
-            if self.sound == op.sound {
-                similarity += 1;
-            }
+    pub fn color(&self) -> PieceColor {
+        self.get()
+    }
 
-            if self.color == op.color {
-                similarity += 1;
-            }
-        }
+    pub fn shape(&self) -> PieceShape {
+        self.get()
+    }
 
-        similarity
+    pub fn sound(&self) -> PieceSound {
+        self.get()
     }
-}
 
-impl Default for GamePiece {
-    fn default() -> Self {
-        GamePiece::random()
+    pub fn is_wildcard(&self) -> bool {
+        self.special == Some(SpecialPiece::Wildcard)
+    }
+
+    /// Do both pieces have the same value for this attribute? Wildcards match anything
+    pub fn shares(&self, kind: AttributeKind, other: &GamePiece) -> bool {
+        self.is_wildcard()
+            || other.is_wildcard()
+            || self.value_index(kind) == other.value_index(kind)
+    }
+
+    pub fn random(registry: &AttributeRegistry) -> Self {
+        GamePiece::random_with(registry, &mut rand::rng())
+    }
+
+    pub fn random_with<R: Rng + ?Sized>(registry: &AttributeRegistry, rng: &mut R) -> Self {
+        GamePiece {
+            attributes: AttributeKind::ALL.map(|kind| registry.sample(kind, rng)),
+            special: None,
+            id: Uuid::new_v4(),
+        }
+    }
+
+    fn compare(&self, other: Option<GamePiece>) -> usize {
+        if let Some(op) = other {
+            AttributeKind::ALL
+                .iter()
+                .filter(|kind| self.shares(**kind, &op))
+                .count()
+        } else {
+            0
+        }
     }
 }
 
@@ -194,30 +341,48 @@ impl Default for GamePiece {
 #[derive(Resource, Debug, Clone)]
 pub struct GameGrid {
     pub cells: Grid<Option<GamePiece>>,
+    /// Where the board places the bomb, picked at random when not set
+    pub bomb: Option<(usize, usize)>,
 }
 
 impl Default for GameGrid {
     fn default() -> Self {
-        Self::new()
+        Self::new(&AttributeRegistry::default())
     }
 }
 
 impl GameGrid {
-    pub fn new() -> Self {
-        fn initialize_positions(gg: &mut GameGrid) {
+    pub fn new(registry: &AttributeRegistry) -> Self {
+        fn initialize_positions(gg: &mut GameGrid, registry: &AttributeRegistry) {
             for cell in gg.cells.iter_mut() {
-                let mut rng = rand::rng();
-
                 if cell.is_none() {
-                    *cell = Some(GamePiece::new(rng.random(), rng.random(), rng.random()));
+                    *cell = Some(GamePiece::random(registry));
                 }
             }
         }
 
         let mut grid = GameGrid {
             cells: Grid::new(GRID_HEIGHT, GRID_WIDTH),
+            bomb: None,
+        };
+        initialize_positions(&mut grid, registry);
+        grid
+    }
+
+    /// Deals a board and bomb position from a seed. The same seed always deals the same board
+    pub fn seeded(registry: &AttributeRegistry, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut grid = GameGrid {
+            cells: Grid::new(GRID_HEIGHT, GRID_WIDTH),
+            bomb: None,
         };
-        initialize_positions(&mut grid);
+        for cell in grid.cells.iter_mut() {
+            *cell = Some(GamePiece::random_with(registry, &mut rng));
+        }
+        grid.bomb = Some((
+            rng.random_range(0..grid.cells.rows()),
+            rng.random_range(0..grid.cells.cols()),
+        ));
         grid
     }
 
@@ -251,7 +416,11 @@ impl GameGrid {
         self.cells.get_mut(y, x)?.as_mut()
     }
 
-    pub fn collapse_column(grid: Grid<Option<GamePiece>>, col: usize) -> Vec<Option<GamePiece>> {
+    pub fn collapse_column(
+        grid: Grid<Option<GamePiece>>,
+        col: usize,
+        registry: &AttributeRegistry,
+    ) -> Vec<Option<GamePiece>> {
         let mut col_vec: Vec<Option<GamePiece>> = vec![];
         for cell in grid.iter_row(col) {
             if cell.is_some() {
@@ -262,13 +431,60 @@ impl GameGrid {
         let diff = grid.cols() - col_vec.len();
 
         for _i in 0..diff {
-            let mut rng = rand::rng();
-            let piece = Some(GamePiece::new(rng.random(), rng.random(), rng.random()));
-            col_vec.push(piece);
+            col_vec.push(Some(GamePiece::random(registry)));
         }
         col_vec
     }
 
+    /// Turns the piece at this position into a special piece
+    pub fn place_special(&mut self, position: Vec2, special: SpecialPiece) {
+        if let Some(Some(piece)) = self.cells.get_mut(position.x as usize, position.y as usize) {
+            piece.special = Some(special);
+        }
+    }
+
+    /// Picks a random bomb cell that isn't holding a special piece, so none is spawned under the bomb
+    pub fn place_bomb<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let free: Vec<(usize, usize)> = self
+            .cells
+            .indexed_iter()
+            .filter(|(_, cell)| cell.is_none_or(|piece| piece.special.is_none()))
+            .map(|(i, _)| i)
+            .collect();
+        if free.is_empty() {
+            return;
+        }
+        self.bomb = Some(free[rng.random_range(0..free.len())]);
+    }
+
+    /// Every extra cell cleared by the special pieces in a line
+    pub fn special_targets(&self, line: &[Vec2]) -> Vec<Vec2> {
+        let mut targets: Vec<Vec2> = vec![];
+        for position in line {
+            let Some(piece) = self.get_piece(*position) else {
+                continue;
+            };
+
+            for (i, cell) in self.cells.indexed_iter() {
+                let cell_position = Vec2::new(i.0 as f32, i.1 as f32);
+                let hit = match piece.special {
+                    Some(SpecialPiece::LineBlaster) => {
+                        cell_position.x == position.x || cell_position.y == position.y
+                    }
+                    Some(SpecialPiece::ColorBomb) => {
+                        cell.is_some_and(|other| other.color() == piece.color())
+                    }
+                    _ => false,
+                };
+
+                if hit && !line.contains(&cell_position) && !targets.contains(&cell_position) {
+                    targets.push(cell_position);
+                }
+            }
+        }
+        targets
+    }
+
     pub fn pop_cell(&mut self, target: Vec2) {
         let row = target.y as usize;
         let col = target.x as usize;
@@ -1,62 +1,165 @@
-
 use bevy::prelude::*;
 
 use crate::{
-    audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings},
+    actions::{Action, ActionInput},
+    audio_server::components::{EffectName, PlayNoteEvent, PlaySoundEffectEvent, SfxSettings},
     game::{
         GameState,
+        attributes::{AttributeKind, AttributeRegistry, AttributeRender},
         bomb::BombPiece,
-        cell_line::{CellLine, CellVisitedEvent},
+        cell_line::{self, CellLine, CellVisitedEvent, LineCompletedEvent},
         game_board::BoardPiece,
         game_grid::*,
+        inspector::InspectedCell,
+        pause::PauseState,
     },
+    screen::components::ScreenState,
 };
 
 pub struct InputHandlerPlugin;
 
 impl Plugin for InputHandlerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, handle_input);
+        app.insert_resource(DragLine::default())
+            .add_systems(
+                Update,
+                (
+                    handle_input,
+                    end_drag.before(cell_line::update_cell_visitation),
+                )
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnExit(ScreenState::Game), reset_drag);
     }
 }
 
+/// Whether the pointer that started the current line is still held down.
+/// While it is, moving over pieces extends the line
+#[derive(Resource, Debug, Default)]
+pub struct DragLine {
+    pub active: bool,
+    /// Whether the line has been extended by dragging since the press
+    pub extended: bool,
+}
+
+impl DragLine {
+    fn press(&mut self) {
+        self.active = true;
+        self.extended = false;
+    }
+}
+
+//Systems
+
 pub fn handle_input(
-    input: Res<ButtonInput<KeyCode>>,
+    actions: ActionInput,
     mut next_state: ResMut<NextState<GameState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
     mut cell_line: ResMut<CellLine>,
 ) {
-    if input.just_pressed(KeyCode::Escape) {
-        next_state.set(GameState::FreePick);
-        cell_line.visited.clear();
+    if actions.just_pressed(Action::Pause) {
+        next_pause.set(PauseState::Paused);
+        return;
+    }
+
+    //Cancel drops a line in progress, otherwise it pauses
+    if actions.just_pressed(Action::Cancel) {
+        if cell_line.visited.is_empty() {
+            next_pause.set(PauseState::Paused);
+        } else {
+            next_state.set(GameState::FreePick);
+            cell_line.clear();
+        }
     }
 }
 
+///Releasing the mouse button or lifting a finger commits the line, if it was extended by dragging during this press.
+///A press and release without dragging is a plain click, and leaves the line open.
+///A line that completed itself while dragging ends the drag, so letting go doesn't send it again
+fn end_drag(
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut drag: ResMut<DragLine>,
+    mut cell_line: ResMut<CellLine>,
+    mut ew_line_complete: EventWriter<LineCompletedEvent>,
+) {
+    if cell_line.committed {
+        if drag.active {
+            *drag = DragLine::default();
+        }
+        return;
+    }
+    if !drag.active || !(mouse.just_released(MouseButton::Left) || touches.any_just_released()) {
+        return;
+    }
+
+    let extended = drag.extended;
+    *drag = DragLine::default();
+    if extended && cell_line.visited.len() > 1 {
+        cell_line.commit(&mut ew_line_complete);
+    }
+}
+
+fn reset_drag(mut drag: ResMut<DragLine>) {
+    *drag = DragLine::default();
+}
+
 pub fn on_over(
     trigger: Trigger<Pointer<Over>>,
     piece_q: Query<(Entity, &BoardPiece)>,
-    bomb: Single<Entity, With<BombPiece>>,
+    bomb: Single<(Entity, &BombPiece)>,
+    registry: Res<AttributeRegistry>,
+    pause_state: Res<State<PauseState>>,
+    mut drag: ResMut<DragLine>,
+    mut cell_line: ResMut<CellLine>,
+    grid: Res<GameGrid>,
+    mut inspected: ResMut<InspectedCell>,
+    mut ew_visited: EventWriter<CellVisitedEvent>,
     mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    mut ew_note_player: EventWriter<PlayNoteEvent>,
 ) {
-    if trigger.target() == *bomb {
+    if *pause_state.get() == PauseState::Paused {
+        return;
+    }
+    let (bomb_entity, bomb_piece) = *bomb;
+    //Dragging only takes steps the line accepts, so sweeping past a piece that doesn't match won't end it
+    let dragging = drag.active && !cell_line.visited.is_empty() && !cell_line.committed;
+
+    if trigger.target() == bomb_entity {
+        inspected.0 = Some(bomb_piece.position());
         ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
             EffectName::Fuse,
             Some(true),
             None,
         )));
+        if dragging && cell_line.accepts_bomb(bomb_piece.position()) {
+            cell_line.visited.push(bomb_piece.position());
+            drag.extended = true;
+        }
     }
     for (entity, piece) in piece_q.iter() {
         if trigger.target() == entity {
-            let note = match piece.game_piece.sound {
-                PieceSound::A => EffectName::NoteA,
-                PieceSound::B => EffectName::NoteB,
-                PieceSound::C => EffectName::NoteC,
-                PieceSound::D => EffectName::NoteD,
-                PieceSound::E => EffectName::NoteE,
-                PieceSound::F => EffectName::NoteF,
-                PieceSound::G => EffectName::NoteG,
-            };
-
-            ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(note, None, None)));
+            inspected.0 = Some(grid.get_position(&piece.game_piece));
+            play_piece_note(&piece.game_piece, &registry, &mut ew_note_player);
+            if dragging && cell_line.accepts(&piece.game_piece, &grid, bomb_piece.position()) {
+                ew_visited.write(CellVisitedEvent(grid.get_position(&piece.game_piece)));
+                drag.extended = true;
+            }
+        }
+    }
+}
+
+/// Plays the note of every attribute of the piece that renders as one
+pub fn play_piece_note(
+    piece: &GamePiece,
+    registry: &AttributeRegistry,
+    ew_note_player: &mut EventWriter<PlayNoteEvent>,
+) {
+    for kind in AttributeKind::ALL {
+        let value = registry.value_at(kind, piece.value_index(kind));
+        if let AttributeRender::Note(pitch) = &value.render {
+            ew_note_player.write(PlayNoteEvent(*pitch));
         }
     }
 }
@@ -67,7 +170,13 @@ pub fn on_click(
     mut ew_init_cell_line: EventWriter<CellVisitedEvent>,
     piece_q: Query<(Entity, &BoardPiece)>,
     grid: Res<GameGrid>,
+    pause_state: Res<State<PauseState>>,
+    mut drag: ResMut<DragLine>,
 ) {
+    if *pause_state.get() == PauseState::Paused {
+        return;
+    }
+    drag.press();
     if state.get() == &GameState::FreePick {
         next_state.set(GameState::PickNext);
         for (entity, piece) in piece_q.iter() {
@@ -90,9 +199,17 @@ pub fn on_bomb_click(
     _trigger: Trigger<Pointer<Pressed>>,
     mut cell_line: ResMut<CellLine>,
     bomb_q: Query<&BombPiece>,
+    pause_state: Res<State<PauseState>>,
+    mut drag: ResMut<DragLine>,
 ) {
+    if *pause_state.get() == PauseState::Paused {
+        return;
+    }
+    drag.press();
     for bomb in bomb_q {
         let nv = Vec2::new(bomb.0.0 as f32, bomb.0.1 as f32);
-        cell_line.visited.push(nv);
+        if cell_line.accepts_bomb(nv) {
+            cell_line.visited.push(nv);
+        }
     }
 }
@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        HudDisplay, SpawnHud,
+        attributes::{AttributeKind, AttributeRegistry},
+        bomb::BombPiece,
+        cell_line::CellLine,
+        cursor::GridCursor,
+        game_grid::GameGrid,
+    },
+    locale::{LocalizedText, name_key},
+    screen::components::ScreenState,
+    ui_theme::{NodeRole, UiTheme},
+};
+
+const MATCH_COLOR: Color = Color::srgb(0.4, 1.0, 0.4);
+const MISMATCH_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InspectedCell::default())
+            .add_systems(OnEnter(ScreenState::Game), reset_inspected)
+            .add_systems(SpawnHud, setup_inspector_display)
+            .add_systems(
+                Update,
+                (follow_cursor, update_inspector_display)
+                    .chain()
+                    .run_if(in_state(ScreenState::Game)),
+            );
+    }
+}
+
+/// The cell last hovered with the pointer or picked with the grid cursor
+#[derive(Resource, Debug, Default)]
+pub struct InspectedCell(pub Option<Vec2>);
+
+//Marker for the inspector panel title
+#[derive(Component)]
+pub struct InspectorTitle;
+
+//Marker for the line showing one attribute of the inspected piece
+#[derive(Component)]
+pub struct InspectorLine(AttributeKind);
+
+//Systems
+
+fn reset_inspected(mut inspected: ResMut<InspectedCell>) {
+    inspected.0 = None;
+}
+
+fn setup_inspector_display(mut commands: Commands, theme: Res<UiTheme>) {
+    let panel = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::VMin(5.0),
+                right: Val::VMin(5.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(theme.hud_background),
+            NodeRole::Panel,
+            HudDisplay,
+            StateScoped(ScreenState::Game),
+        ))
+        .id();
+
+    let title = commands
+        .spawn((Text::default(), theme.text_style(), InspectorTitle))
+        .id();
+    commands.entity(panel).add_child(title);
+
+    for kind in AttributeKind::ALL {
+        let line = commands
+            .spawn((Text::default(), theme.text_style(), InspectorLine(kind)))
+            .id();
+        commands.entity(panel).add_child(line);
+    }
+}
+
+///Keyboard and gamepad players inspect whatever the grid cursor is on
+fn follow_cursor(cursor: Res<GridCursor>, mut inspected: ResMut<InspectedCell>) {
+    if cursor.is_changed() && cursor.visible {
+        inspected.0 = Some(cursor.cell());
+    }
+}
+
+///Lists the inspected piece's attributes, marking the ones it shares with the head of the line
+fn update_inspector_display(
+    mut commands: Commands,
+    title: Single<Entity, With<InspectorTitle>>,
+    new_display_q: Query<(), Added<InspectorTitle>>,
+    mut line_q: Query<(Entity, &InspectorLine, &mut TextColor)>,
+    inspected: Res<InspectedCell>,
+    cell_line: Res<CellLine>,
+    grid: Res<GameGrid>,
+    registry: Res<AttributeRegistry>,
+    bomb: Single<&BombPiece>,
+    theme: Res<UiTheme>,
+) {
+    //A rebuilt HUD needs filling in even if nothing else changed
+    if !inspected.is_changed()
+        && !cell_line.is_changed()
+        && !theme.is_changed()
+        && !grid.is_changed()
+        && new_display_q.is_empty()
+    {
+        return;
+    }
+
+    let piece = inspected.0.and_then(|cell| *grid.get_piece(cell));
+    let head = cell_line
+        .visited
+        .last()
+        .filter(|head| Some(**head) != inspected.0)
+        .and_then(|head| *grid.get_piece(*head));
+
+    let title_key = match (inspected.0, piece) {
+        (_, Some(_)) => "inspector.piece",
+        (Some(cell), None) if cell == bomb.position() => "inspector.bomb",
+        _ => "inspector.hover",
+    };
+    commands
+        .entity(*title)
+        .insert(LocalizedText::new(title_key));
+
+    for (entity, line, mut text_color) in line_q.iter_mut() {
+        let Some(piece) = piece else {
+            commands
+                .entity(entity)
+                .insert(LocalizedText::new("inspector.empty_line"));
+            continue;
+        };
+        let def = registry.def(line.0);
+        let value = registry.value_at(line.0, piece.value_index(line.0));
+        let (key, color) = match head {
+            Some(head) if piece.shares(line.0, &head) => ("inspector.matches", MATCH_COLOR),
+            Some(_) => ("inspector.no_match", MISMATCH_COLOR),
+            None => ("inspector.attribute", theme.text_color),
+        };
+        commands.entity(entity).insert(
+            LocalizedText::new(key)
+                .arg_key("attribute", name_key("attribute", def.name))
+                .arg_key("value", name_key("value", value.name)),
+        );
+        *text_color = TextColor(color);
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        attributes::{AttributeKind, AttributeRegistry, AttributeRender, PieceAttribute},
+        game_board::MaterialMap,
+        game_grid::PieceColor,
+    },
+    save,
+};
+
+const SAVE_NAME: &str = "palette";
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<PaletteSettings>(SAVE_NAME))
+            .add_systems(
+                Update,
+                (apply_palette, save_palette).run_if(resource_changed::<PaletteSettings>),
+            );
+    }
+}
+
+/// Sets of piece colors. Every set other than Standard keeps its colors apart for one kind of colorblindness
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
+
+impl ColorPalette {
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            ColorPalette::Standard => "palette.standard",
+            ColorPalette::Deuteranopia => "palette.deuteranopia",
+            ColorPalette::Protanopia => "palette.protanopia",
+            ColorPalette::Tritanopia => "palette.tritanopia",
+            ColorPalette::HighContrast => "palette.high_contrast",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ColorPalette::Standard => ColorPalette::Deuteranopia,
+            ColorPalette::Deuteranopia => ColorPalette::Protanopia,
+            ColorPalette::Protanopia => ColorPalette::Tritanopia,
+            ColorPalette::Tritanopia => ColorPalette::HighContrast,
+            ColorPalette::HighContrast => ColorPalette::Standard,
+        }
+    }
+
+    /// The Standard palette is whatever the attribute registry tints each color
+    pub fn color(&self, color: PieceColor, registry: &AttributeRegistry) -> Color {
+        match self {
+            ColorPalette::Standard => {
+                match registry
+                    .value_at(AttributeKind::Color, color.index())
+                    .render
+                {
+                    AttributeRender::Tint(tint) => tint,
+                    _ => color.to_color(),
+                }
+            }
+            // Okabe-Ito, with white in place of black so it shows on the dark board
+            ColorPalette::Deuteranopia => match color {
+                PieceColor::Pink => Color::srgb(0.80, 0.47, 0.65),
+                PieceColor::Green => Color::srgb(0.0, 0.62, 0.45),
+                PieceColor::Blue => Color::srgb(0.0, 0.45, 0.70),
+                PieceColor::Yellow => Color::srgb(0.94, 0.89, 0.26),
+                PieceColor::Orange => Color::srgb(0.90, 0.62, 0.0),
+                PieceColor::Purple => Color::srgb(1.0, 1.0, 1.0),
+                PieceColor::Cyan => Color::srgb(0.34, 0.71, 0.91),
+                PieceColor::Red => Color::srgb(0.84, 0.37, 0.0),
+            },
+            // Reds look darker without L cones, so they are lifted
+            ColorPalette::Protanopia => match color {
+                PieceColor::Pink => Color::srgb(1.0, 0.6, 0.8),
+                PieceColor::Green => Color::srgb(0.0, 0.62, 0.45),
+                PieceColor::Blue => Color::srgb(0.0, 0.45, 0.70),
+                PieceColor::Yellow => Color::srgb(0.94, 0.89, 0.26),
+                PieceColor::Orange => Color::srgb(0.90, 0.62, 0.0),
+                PieceColor::Purple => Color::srgb(1.0, 1.0, 1.0),
+                PieceColor::Cyan => Color::srgb(0.34, 0.71, 0.91),
+                PieceColor::Red => Color::srgb(1.0, 0.45, 0.1),
+            },
+            // Kept apart on the red-green axis, since blue and yellow run together
+            ColorPalette::Tritanopia => match color {
+                PieceColor::Pink => Color::srgb(1.0, 0.65, 0.75),
+                PieceColor::Green => Color::srgb(0.0, 0.45, 0.45),
+                PieceColor::Blue => Color::srgb(0.35, 0.85, 0.85),
+                PieceColor::Yellow => Color::srgb(1.0, 1.0, 1.0),
+                PieceColor::Orange => Color::srgb(0.95, 0.3, 0.2),
+                PieceColor::Purple => Color::srgb(0.55, 0.0, 0.25),
+                PieceColor::Cyan => Color::srgb(0.5, 0.5, 0.5),
+                PieceColor::Red => Color::srgb(0.85, 0.0, 0.0),
+            },
+            ColorPalette::HighContrast => match color {
+                PieceColor::Pink => Color::srgb(1.0, 0.0, 1.0),
+                PieceColor::Green => Color::srgb(0.0, 1.0, 0.0),
+                PieceColor::Blue => Color::srgb(0.0, 0.3, 1.0),
+                PieceColor::Yellow => Color::srgb(1.0, 1.0, 0.0),
+                PieceColor::Orange => Color::srgb(1.0, 0.5, 0.0),
+                PieceColor::Purple => Color::srgb(1.0, 1.0, 1.0),
+                PieceColor::Cyan => Color::srgb(0.0, 1.0, 1.0),
+                PieceColor::Red => Color::srgb(1.0, 0.0, 0.0),
+            },
+        }
+    }
+}
+
+/// How piece colors are drawn
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct PaletteSettings {
+    pub palette: ColorPalette,
+    /// Gives each color its own pattern, so colors can be told apart without hue
+    pub patterns: bool,
+}
+
+impl PaletteSettings {
+    pub fn texture_path(&self, color: PieceColor) -> &'static str {
+        if self.patterns {
+            color.pattern_path()
+        } else {
+            "image/purple_concrete.png"
+        }
+    }
+
+    /// Paints a piece material in the given color
+    pub fn paint(
+        &self,
+        material: &mut StandardMaterial,
+        color: PieceColor,
+        registry: &AttributeRegistry,
+        asset_server: &AssetServer,
+    ) {
+        let tint = self.palette.color(color, registry);
+        material.base_color = tint;
+        material.base_color_texture = Some(asset_server.load(self.texture_path(color)));
+        material.emissive = tint.to_linear();
+        //The glow would wash a pattern out unless it is patterned too
+        material.emissive_texture = self
+            .patterns
+            .then(|| asset_server.load(color.pattern_path()));
+    }
+}
+
+//Systems
+
+///Repaints the shared piece materials, so pieces already on the board change too
+fn apply_palette(
+    settings: Res<PaletteSettings>,
+    material_map: Res<MaterialMap>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    registry: Res<AttributeRegistry>,
+    asset_server: Res<AssetServer>,
+) {
+    for (color, handle) in material_map.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            settings.paint(material, *color, &registry, &asset_server);
+        }
+    }
+}
+
+fn save_palette(settings: Res<PaletteSettings>) {
+    save::save(SAVE_NAME, &*settings);
+}
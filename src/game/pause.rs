@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::{Action, ActionInput},
+    audio_server::{
+        components::{AudioSettings, EffectName, PlaySoundEffectEvent, SfxSettings, SoundEffect},
+        synth::NoteSettings,
+    },
+    game::{NewGameEvent, cell_line::ChainRule, palette::PaletteSettings},
+    locale::LocalizedText,
+    screen::{components::ScreenState, menu::create_button_node, options::spawn_options},
+    ui_theme::{UiScaleSettings, UiTheme},
+};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_state(PauseState::Running)
+            .add_systems(
+                OnEnter(PauseState::Paused),
+                (setup_pause_overlay, pause_sound_effects),
+            )
+            .add_systems(
+                Update,
+                (pause_buttons_system, handle_pause_input).run_if(in_state(PauseState::Paused)),
+            )
+            .add_systems(
+                OnExit(PauseState::Paused),
+                (deconstruct_pause_overlay, resume_sound_effects),
+            )
+            .add_systems(OnExit(ScreenState::Game), unpause);
+    }
+}
+
+/// Whether play on the game screen is running or halted behind the pause overlay
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PauseState {
+    Running,
+    Paused,
+}
+
+//Marker for the pause overlay root
+#[derive(Component)]
+pub struct PauseOverlay;
+
+//Marker for the options panel shown inside the pause overlay
+#[derive(Component)]
+pub struct PauseOptionsPanel;
+
+#[derive(Component, Debug)]
+pub enum PauseButtonType {
+    Resume,
+    Options,
+    Restart,
+    QuitToMenu,
+    Back,
+}
+
+//Systems
+
+fn setup_pause_overlay(mut commands: Commands, theme: Res<UiTheme>) {
+    info!("Pausing game");
+    let overlay = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.7)),
+            GlobalZIndex(10),
+            PauseOverlay,
+            StateScoped(ScreenState::Game),
+        ))
+        .id();
+    spawn_pause_menu(&mut commands, overlay, &theme);
+}
+
+fn spawn_pause_menu(commands: &mut Commands, overlay: Entity, theme: &Res<UiTheme>) {
+    let title_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            LocalizedText::new("pause.title"),
+            theme.text_style(),
+        ))
+        .id();
+    let resume_button = create_button_node(
+        PauseButtonType::Resume,
+        LocalizedText::new("pause.resume"),
+        commands,
+        theme,
+    );
+    let options_button = create_button_node(
+        PauseButtonType::Options,
+        LocalizedText::new("pause.options"),
+        commands,
+        theme,
+    );
+    let restart_button = create_button_node(
+        PauseButtonType::Restart,
+        LocalizedText::new("pause.restart"),
+        commands,
+        theme,
+    );
+    let quit_button = create_button_node(
+        PauseButtonType::QuitToMenu,
+        LocalizedText::new("pause.quit_to_menu"),
+        commands,
+        theme,
+    );
+
+    commands.entity(overlay).add_children(&[
+        title_text,
+        resume_button,
+        options_button,
+        restart_button,
+        quit_button,
+    ]);
+}
+
+fn spawn_pause_options(
+    commands: &mut Commands,
+    overlay: Entity,
+    theme: &Res<UiTheme>,
+    chain_rule: &ChainRule,
+    palette: &PaletteSettings,
+    ui_scale: &UiScaleSettings,
+    audio_settings: &AudioSettings,
+    note_settings: &NoteSettings,
+) {
+    let panel = commands
+        .spawn((
+            Node {
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            PauseOptionsPanel,
+        ))
+        .id();
+    spawn_options(
+        commands,
+        panel,
+        theme,
+        chain_rule,
+        palette,
+        ui_scale,
+        audio_settings,
+        note_settings,
+    );
+
+    let back_button = create_button_node(
+        PauseButtonType::Back,
+        LocalizedText::new("pause.back"),
+        commands,
+        theme,
+    );
+    commands.entity(panel).add_child(back_button);
+    commands.entity(overlay).add_child(panel);
+}
+
+fn pause_buttons_system(
+    mut commands: Commands,
+    mut interaction_q: Query<
+        (&Interaction, &mut BackgroundColor, &PauseButtonType),
+        (Changed<Interaction>, With<Button>),
+    >,
+    overlay: Single<Entity, With<PauseOverlay>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_screen: ResMut<NextState<ScreenState>>,
+    mut ew_new_game: EventWriter<NewGameEvent>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    chain_rule: Res<ChainRule>,
+    palette: Res<PaletteSettings>,
+    ui_scale: Res<UiScaleSettings>,
+    audio_settings: Res<AudioSettings>,
+    note_settings: Res<NoteSettings>,
+    theme: Res<UiTheme>,
+) {
+    for (interaction, mut bgcolor, button_type) in &mut interaction_q {
+        match *interaction {
+            Interaction::Pressed => {
+                *bgcolor = theme.button_background_pressed.into();
+                match button_type {
+                    PauseButtonType::Resume => next_pause.set(PauseState::Running),
+                    PauseButtonType::Options => {
+                        commands.entity(*overlay).despawn_related::<Children>();
+                        spawn_pause_options(
+                            &mut commands,
+                            *overlay,
+                            &theme,
+                            &chain_rule,
+                            &palette,
+                            &ui_scale,
+                            &audio_settings,
+                            &note_settings,
+                        );
+                    }
+                    PauseButtonType::Restart => {
+                        ew_new_game.write_default();
+                        next_pause.set(PauseState::Running);
+                    }
+                    PauseButtonType::QuitToMenu => next_screen.set(ScreenState::Menu),
+                    PauseButtonType::Back => {
+                        commands.entity(*overlay).despawn_related::<Children>();
+                        spawn_pause_menu(&mut commands, *overlay, &theme);
+                    }
+                }
+                ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+                    EffectName::UiConfirm,
+                    Some(false),
+                    None,
+                )));
+            }
+            Interaction::Hovered => {
+                ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+                    EffectName::Click,
+                    Some(false),
+                    None,
+                )));
+                *bgcolor = theme.button_background_hover.into();
+            }
+            Interaction::None => {
+                *bgcolor = theme.button_background_normal.into();
+            }
+        }
+    }
+}
+
+///Back leaves the options panel, or resumes from the pause menu. Pause always resumes
+fn handle_pause_input(
+    mut commands: Commands,
+    actions: ActionInput,
+    overlay: Single<Entity, With<PauseOverlay>>,
+    options_q: Query<(), With<PauseOptionsPanel>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    theme: Res<UiTheme>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        next_pause.set(PauseState::Running);
+        return;
+    }
+    if !actions.just_pressed(Action::Back) {
+        return;
+    }
+
+    if options_q.is_empty() {
+        next_pause.set(PauseState::Running);
+    } else {
+        commands.entity(*overlay).despawn_related::<Children>();
+        spawn_pause_menu(&mut commands, *overlay, &theme);
+    }
+}
+
+fn deconstruct_pause_overlay(mut commands: Commands, overlay_q: Query<Entity, With<PauseOverlay>>) {
+    info!("Resuming game");
+    for overlay in overlay_q.iter() {
+        commands.entity(overlay).despawn();
+    }
+}
+
+//Holds any sound effect that is still playing, like a burning fuse
+fn pause_sound_effects(sfx_q: Query<&AudioSink, With<SoundEffect>>) {
+    for sink in sfx_q.iter() {
+        sink.pause();
+    }
+}
+
+fn resume_sound_effects(sfx_q: Query<&AudioSink, With<SoundEffect>>) {
+    for sink in sfx_q.iter() {
+        sink.play();
+    }
+}
+
+fn unpause(mut next_pause: ResMut<NextState<PauseState>>) {
+    next_pause.set(PauseState::Running);
+}
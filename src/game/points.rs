@@ -1,16 +1,21 @@
-
 use bevy::prelude::*;
 
 use crate::{
     game::{
+        HudDisplay, SpawnHud,
         bomb::{BombPiece, MatchBomb},
-        cell_line::{CellLine, LineCompletedEvent},
+        cell_line::{self, CellLine, LineCompletedEvent},
         game_grid::GameGrid,
+        rules::GameRules,
     },
+    locale::LocalizedText,
     screen::components::ScreenState,
+    ui_theme::UiTheme,
 };
 
 const BASE_SCORE: i32 = 10;
+//Awarded for every extra cell cleared by a special piece
+const SPECIAL_BONUS: i32 = 15;
 
 pub struct PointsPlugin;
 
@@ -19,12 +24,15 @@ impl Plugin for PointsPlugin {
         app.insert_resource(Score::new())
             .add_systems(
                 Update,
-                on_line_complete.run_if(on_event::<LineCompletedEvent>),
+                on_line_complete
+                    .run_if(on_event::<LineCompletedEvent>)
+                    .before(cell_line::on_line_complete),
             )
-            .add_systems(OnEnter(ScreenState::Game), setup_score_display)
+            .add_systems(SpawnHud, (setup_score_display, setup_line_length_display))
             .add_systems(
                 Update,
-                update_score_display.run_if(in_state(ScreenState::Game)),
+                (update_score_display, update_line_length_display)
+                    .run_if(in_state(ScreenState::Game)),
             );
     }
 }
@@ -48,6 +56,10 @@ impl Score {
             doubles: 0,
         }
     }
+
+    pub fn reset(&mut self) {
+        *self = Score::new();
+    }
 }
 
 //Because the name floating points was already taken by math dorks
@@ -56,11 +68,14 @@ pub struct ScrollingPoints(pub u32);
 
 #[derive(Component)]
 pub struct ScoreDisplay;
+
+#[derive(Component)]
+pub struct LineLengthDisplay;
 //Systems
 //
 //
 //
-fn setup_score_display(mut commands: Commands, score: Res<Score>) {
+fn setup_score_display(mut commands: Commands, score: Res<Score>, theme: Res<UiTheme>) {
     commands.spawn((
         Node {
             position_type: PositionType::Absolute,
@@ -72,15 +87,59 @@ fn setup_score_display(mut commands: Commands, score: Res<Score>) {
             ..default()
         },
         Text::from(score.total.to_string()),
-        TextFont {
-            font_size: 24.0,
-            ..default()
-        },
+        theme.hud_text_style(),
         ScoreDisplay,
+        HudDisplay,
+        StateScoped(ScreenState::Game),
         Transform::from_translation(Vec3::ZERO),
     ));
 }
 
+fn setup_line_length_display(mut commands: Commands, theme: Res<UiTheme>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::VMin(5.0),
+            left: Val::VMin(5.0),
+            ..default()
+        },
+        Text::default(),
+        theme.hud_text_style(),
+        LineLengthDisplay,
+        HudDisplay,
+        StateScoped(ScreenState::Game),
+    ));
+}
+
+fn update_line_length_display(
+    mut commands: Commands,
+    display: Single<Entity, With<LineLengthDisplay>>,
+    cell_line: Res<CellLine>,
+    rules: Res<GameRules>,
+) {
+    if !cell_line.is_changed() {
+        return;
+    }
+
+    let length = cell_line.cells().len();
+    //Show progress towards the minimum first, then towards each bonus tier
+    let display_text = if length == 0 {
+        LocalizedText::new("hud.no_line")
+    } else if !rules.is_scoring(length) {
+        LocalizedText::new("hud.line_to_minimum")
+            .arg("length", length)
+            .arg("target", rules.min_line_length)
+    } else if let Some(tier) = rules.next_tier(length) {
+        LocalizedText::new("hud.line_to_tier")
+            .arg("length", length)
+            .arg("target", tier.length)
+            .arg("bonus", tier.bonus)
+    } else {
+        LocalizedText::new("hud.line_max_bonus").arg("length", length)
+    };
+    commands.entity(*display).insert(display_text);
+}
+
 fn update_score_display(
     mut commands: Commands,
     text_q: Query<Entity, With<ScoreDisplay>>,
@@ -95,22 +154,39 @@ fn update_score_display(
     }
 }
 
-fn on_line_complete(
+pub fn on_line_complete(
     cell_line: ResMut<CellLine>,
     grid: Res<GameGrid>,
+    rules: Res<GameRules>,
     mut score: ResMut<Score>,
     mut bomb: ResMut<MatchBomb>,
     bomb_piece: Single<&BombPiece>,
 ) {
-    let mut total = 0;
-    for (i, position) in cell_line.visited.iter().enumerate() {
-        //Can't match the last element
-        total = BASE_SCORE * cell_line.visited.len() as i32 * ((score.perfects + 1) * 2)
-            + (score.doubles + 1);
-        score.total += total;
+    let length = cell_line.cells().len();
+    if !rules.is_scoring(length) {
+        bomb.decrement_by(rules.short_line_turn_cost);
+        return;
     }
 
-    if cell_line.visited.contains(&bomb_piece.position()) {
+    let enclosed = cell_line.enclosed_cells(&grid);
+    let mut blasted = grid.special_targets(cell_line.cells());
+    blasted.retain(|cell| !enclosed.contains(cell));
+
+    let total = (BASE_SCORE * length as i32 * ((score.perfects + 1) * 2)
+        + (score.doubles + 1)
+        + rules.length_bonus(length)
+        + SPECIAL_BONUS * blasted.len() as i32
+        + rules.capture_bonus * enclosed.len() as i32)
+        * cell_line.rule.score_multiplier();
+    score.total += total;
+
+    //Capturing the bomb inside a loop defuses it outright
+    if enclosed.contains(&bomb_piece.position()) {
+        let remaining = bomb.points_remaining();
+        bomb.sub(remaining);
+    } else if cell_line.visited.contains(&bomb_piece.position())
+        || blasted.contains(&bomb_piece.position())
+    {
         bomb.sub(total.try_into().unwrap());
     }
     bomb.decrement();
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+/// A bonus awarded once a line reaches this many cells
+#[derive(Debug, Clone, Copy)]
+pub struct LengthBonus {
+    pub length: usize,
+    pub bonus: i32,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct GameRules {
+    /// Lines shorter than this fail instead of popping
+    pub min_line_length: usize,
+    /// Bonus tiers, sorted by length. Only the highest tier reached is awarded
+    pub length_bonuses: Vec<LengthBonus>,
+    /// Turns taken off the bomb when a line is too short
+    pub short_line_turn_cost: u64,
+    /// Awarded for every cell captured by a closed loop
+    pub capture_bonus: i32,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules {
+            min_line_length: 4,
+            length_bonuses: vec![
+                LengthBonus {
+                    length: 5,
+                    bonus: 25,
+                },
+                LengthBonus {
+                    length: 7,
+                    bonus: 75,
+                },
+                LengthBonus {
+                    length: 10,
+                    bonus: 200,
+                },
+            ],
+            short_line_turn_cost: 1,
+            capture_bonus: 50,
+        }
+    }
+}
+
+impl GameRules {
+    pub fn is_scoring(&self, length: usize) -> bool {
+        length >= self.min_line_length
+    }
+
+    /// Bonus for the highest tier a line of this length reaches
+    pub fn length_bonus(&self, length: usize) -> i32 {
+        self.length_bonuses
+            .iter()
+            .rev()
+            .find(|tier| length >= tier.length)
+            .map_or(0, |tier| tier.bonus)
+    }
+
+    /// The next tier a line of this length can still reach
+    pub fn next_tier(&self, length: usize) -> Option<&LengthBonus> {
+        self.length_bonuses.iter().find(|tier| tier.length > length)
+    }
+}
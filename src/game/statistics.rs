@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        GameMode, GameState,
+        attributes::{AttributeKind, AttributeRegistry},
+        bomb::BombDefusedEvent,
+        cell_line::{self, CellLine, LineCompletedEvent},
+        game_grid::PopCellEvent,
+        pause::PauseState,
+        rules::GameRules,
+    },
+    locale::{LocalizedText, name_key},
+    save,
+    screen::components::ScreenState,
+    ui_theme::{NodeRole, UiTheme},
+};
+
+const SAVE_NAME: &str = "statistics";
+
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Statistics {
+            run: StatBlock::default(),
+            lifetime: save::load(SAVE_NAME),
+        })
+        .add_systems(OnEnter(ScreenState::Game), start_run)
+        .add_systems(
+            Update,
+            (
+                record_line
+                    .run_if(on_event::<LineCompletedEvent>)
+                    .before(cell_line::on_line_complete),
+                record_pops.run_if(on_event::<PopCellEvent>),
+                record_defuse.run_if(on_event::<BombDefusedEvent>),
+            )
+                .run_if(in_state(GameMode::Standard)),
+        )
+        .add_systems(
+            Update,
+            record_time
+                .run_if(in_state(ScreenState::Game))
+                .run_if(in_state(GameMode::Standard))
+                .run_if(in_state(PauseState::Running))
+                .run_if(not(in_state(GameState::GameOver))),
+        )
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            (record_explosion, save_statistics, setup_run_summary).chain(),
+        )
+        .add_systems(OnExit(ScreenState::Game), save_statistics)
+        .add_systems(Last, save_statistics.run_if(on_event::<AppExit>));
+    }
+}
+
+/// One set of counters, kept for the current run and for the player's lifetime
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct StatBlock {
+    pub lines_drawn: u64,
+    pub total_line_length: u64,
+    pub longest_line: u64,
+    /// Pieces popped per attribute value, indexed by AttributeKind::index then value index
+    pub pops: Vec<Vec<u64>>,
+    pub bombs_defused: u64,
+    pub bombs_exploded: u64,
+    pub turns_played: u64,
+    pub seconds_played: f64,
+}
+
+impl StatBlock {
+    pub fn average_line_length(&self) -> f32 {
+        if self.lines_drawn == 0 {
+            0.0
+        } else {
+            self.total_line_length as f32 / self.lines_drawn as f32
+        }
+    }
+
+    pub fn pops(&self, kind: AttributeKind, index: usize) -> u64 {
+        self.pops
+            .get(kind.index())
+            .and_then(|values| values.get(index))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn record_pop(&mut self, kind: AttributeKind, index: usize) {
+        if self.pops.len() < AttributeKind::COUNT {
+            self.pops.resize(AttributeKind::COUNT, vec![]);
+        }
+        let values = &mut self.pops[kind.index()];
+        if values.len() <= index {
+            values.resize(index + 1, 0);
+        }
+        values[index] += 1;
+    }
+
+    pub fn time_played_label(&self) -> String {
+        let seconds = self.seconds_played as u64;
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60
+        )
+    }
+
+    /// Human readable lines for the stats screen and the game over summary
+    pub fn summary(&self, registry: &AttributeRegistry) -> Vec<LocalizedText> {
+        let mut lines = vec![
+            LocalizedText::new("stats.lines_drawn").arg("value", self.lines_drawn),
+            LocalizedText::new("stats.average_line_length")
+                .arg("value", format!("{:.1}", self.average_line_length())),
+            LocalizedText::new("stats.longest_line").arg("value", self.longest_line),
+            LocalizedText::new("stats.bombs_defused").arg("value", self.bombs_defused),
+            LocalizedText::new("stats.bombs_exploded").arg("value", self.bombs_exploded),
+            LocalizedText::new("stats.turns_played").arg("value", self.turns_played),
+            LocalizedText::new("stats.time_played").arg("value", self.time_played_label()),
+        ];
+
+        for kind in AttributeKind::ALL {
+            let def = registry.def(kind);
+            let counts: Vec<LocalizedText> = def
+                .values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    LocalizedText::new("stats.pop_count")
+                        .arg_key("value", name_key("value", value.name))
+                        .arg("count", self.pops(kind, i))
+                })
+                .collect();
+            lines.push(
+                LocalizedText::new("stats.pops")
+                    .arg_key("attribute", name_key("attribute", def.name))
+                    .arg_list("counts", counts),
+            );
+        }
+        lines
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct Statistics {
+    pub run: StatBlock,
+    pub lifetime: StatBlock,
+}
+
+impl Statistics {
+    pub fn start_run(&mut self) {
+        self.run = StatBlock::default();
+    }
+
+    /// Applies the same change to the run and lifetime counters
+    fn record(&mut self, change: impl Fn(&mut StatBlock)) {
+        change(&mut self.run);
+        change(&mut self.lifetime);
+    }
+}
+
+//Marker for the per-run summary shown on game over
+#[derive(Component)]
+pub struct RunSummaryDisplay;
+
+//Systems
+
+fn start_run(mut statistics: ResMut<Statistics>) {
+    statistics.start_run();
+}
+
+fn record_line(
+    cell_line: Res<CellLine>,
+    rules: Res<GameRules>,
+    mut statistics: ResMut<Statistics>,
+) {
+    let length = cell_line.cells().len() as u64;
+    let scoring = rules.is_scoring(length as usize);
+    statistics.record(|stats| {
+        stats.turns_played += 1;
+        if scoring {
+            stats.lines_drawn += 1;
+            stats.total_line_length += length;
+            stats.longest_line = stats.longest_line.max(length);
+        }
+    });
+}
+
+fn record_pops(mut er_pop_cell: EventReader<PopCellEvent>, mut statistics: ResMut<Statistics>) {
+    for event in er_pop_cell.read() {
+        let Some(piece) = event.1 else {
+            continue;
+        };
+        for kind in AttributeKind::ALL {
+            let index = piece.value_index(kind);
+            statistics.record(|stats| stats.record_pop(kind, index));
+        }
+    }
+}
+
+fn record_defuse(
+    mut er_bomb_defused: EventReader<BombDefusedEvent>,
+    mut statistics: ResMut<Statistics>,
+) {
+    for _ in er_bomb_defused.read() {
+        statistics.record(|stats| stats.bombs_defused += 1);
+    }
+}
+
+fn record_explosion(mut statistics: ResMut<Statistics>) {
+    statistics.record(|stats| stats.bombs_exploded += 1);
+}
+
+fn record_time(mut statistics: ResMut<Statistics>, time: Res<Time>) {
+    let delta = time.delta_secs_f64();
+    statistics.record(|stats| stats.seconds_played += delta);
+}
+
+fn save_statistics(statistics: Res<Statistics>) {
+    save::save(SAVE_NAME, &statistics.lifetime);
+}
+
+fn setup_run_summary(
+    mut commands: Commands,
+    statistics: Res<Statistics>,
+    registry: Res<AttributeRegistry>,
+    theme: Res<UiTheme>,
+) {
+    let panel = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::VMin(20.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(theme.hud_background),
+            NodeRole::Panel,
+            RunSummaryDisplay,
+            StateScoped(ScreenState::Game),
+        ))
+        .id();
+
+    let title_text = commands
+        .spawn((LocalizedText::new("stats.run_title"), theme.text_style()))
+        .id();
+    commands.entity(panel).add_child(title_text);
+
+    for line in statistics.run.summary(&registry) {
+        let line_text = commands.spawn((line, theme.text_style())).id();
+        commands.entity(panel).add_child(line_text);
+    }
+}
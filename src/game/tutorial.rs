@@ -0,0 +1,392 @@
+use bevy::prelude::*;
+use grid::Grid;
+
+use crate::{
+    actions::{Action, ActionInput},
+    audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings},
+    game::{
+        GameMode, GameState, HudDisplay, SpawnHud,
+        bomb::MatchBomb,
+        cell_line::{self, CellLine, CellVisitedEvent, LineCompletedEvent},
+        game_board::{UpdateBoardEvent, setup_board_system, update_board},
+        game_grid::{
+            GRID_HEIGHT, GRID_WIDTH, GameGrid, GamePiece, PieceColor, PieceShape, PieceSound,
+        },
+        points,
+    },
+    locale::LocalizedText,
+    screen::components::ScreenState,
+    ui_theme::{NodeRole, UiTheme},
+};
+
+const TUTORIAL_TURNS: u64 = 5;
+const DEFAULT_THRESHOLD: u64 = 250;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Tutorial::new())
+            .add_systems(
+                OnEnter(ScreenState::Game),
+                start_tutorial
+                    .before(setup_board_system)
+                    .run_if(in_state(GameMode::Tutorial)),
+            )
+            .add_systems(
+                SpawnHud,
+                setup_tutorial_display.run_if(in_state(GameMode::Tutorial)),
+            )
+            .add_systems(
+                Update,
+                (
+                    check_step
+                        .run_if(on_event::<CellVisitedEvent>.or(on_event::<LineCompletedEvent>))
+                        .after(cell_line::update_cell_visitation)
+                        .before(points::on_line_complete)
+                        .before(cell_line::on_line_complete),
+                    load_lesson_board
+                        .run_if(on_event::<UpdateBoardEvent>)
+                        .before(update_board),
+                    draw_highlights,
+                    update_tutorial_display,
+                    replay_tutorial,
+                )
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(GameMode::Tutorial)),
+            );
+    }
+}
+
+/// One scripted board and the line the player is walked through on it.
+/// The texts are string table keys
+pub struct Lesson {
+    pub title: &'static str,
+    pub text: &'static str,
+    /// Shown when the player leaves the highlighted path
+    pub mistake: &'static str,
+    pub pieces: Vec<((usize, usize), GamePiece)>,
+    /// Cells to visit, in order
+    pub path: Vec<(usize, usize)>,
+    /// Cells that look tempting but break a rule
+    pub avoid: Vec<(usize, usize)>,
+    pub bomb: (usize, usize),
+    pub bomb_points: u64,
+}
+
+impl Lesson {
+    /// Builds the fixed board for this lesson.
+    /// Every other cell is filler that shares nothing with the lesson pieces.
+    pub fn board(&self) -> GameGrid {
+        let mut grid = GameGrid {
+            cells: Grid::new(GRID_HEIGHT, GRID_WIDTH),
+            bomb: Some(self.bomb),
+        };
+        for x in 0..GRID_HEIGHT {
+            for y in 0..GRID_WIDTH {
+                let filler = if (x + y) % 2 == 0 {
+                    GamePiece::new(PieceColor::Red, PieceShape::Plus, PieceSound::A)
+                } else {
+                    GamePiece::new(PieceColor::Cyan, PieceShape::Diamond, PieceSound::B)
+                };
+                if let Some(cell) = grid.cells.get_mut(x, y) {
+                    *cell = Some(filler);
+                }
+            }
+        }
+
+        for (position, piece) in self.pieces.iter() {
+            if let Some(cell) = grid.cells.get_mut(position.0, position.1) {
+                *cell = Some(*piece);
+            }
+        }
+        grid
+    }
+
+    fn path_position(&self, step: usize) -> Option<Vec2> {
+        self.path
+            .get(step)
+            .map(|cell| Vec2::new(cell.0 as f32, cell.1 as f32))
+    }
+}
+
+fn lessons() -> Vec<Lesson> {
+    use PieceColor::*;
+    use PieceShape::*;
+    use PieceSound::*;
+
+    vec![
+        Lesson {
+            title: "tutorial.matching.title",
+            text: "tutorial.matching.text",
+            mistake: "tutorial.matching.mistake",
+            pieces: vec![
+                ((2, 4), GamePiece::new(Pink, Circle, C)),
+                ((3, 4), GamePiece::new(Pink, Square, D)),
+                ((4, 4), GamePiece::new(Green, Square, E)),
+                ((5, 4), GamePiece::new(Blue, Triangle, E)),
+            ],
+            path: vec![(2, 4), (3, 4), (4, 4), (5, 4)],
+            avoid: vec![],
+            bomb: (8, 8),
+            bomb_points: DEFAULT_THRESHOLD,
+        },
+        Lesson {
+            title: "tutorial.no_crossing.title",
+            text: "tutorial.no_crossing.text",
+            mistake: "tutorial.no_crossing.mistake",
+            pieces: vec![
+                ((3, 3), GamePiece::new(Pink, Circle, C)),
+                ((4, 4), GamePiece::new(Pink, Square, D)),
+                ((4, 3), GamePiece::new(Green, Square, E)),
+                ((3, 4), GamePiece::new(Yellow, Triangle, E)),
+                ((5, 2), GamePiece::new(Green, Circle, F)),
+            ],
+            path: vec![(3, 3), (4, 4), (4, 3), (5, 2)],
+            avoid: vec![(3, 4)],
+            bomb: (8, 8),
+            bomb_points: DEFAULT_THRESHOLD,
+        },
+        Lesson {
+            title: "tutorial.defusing.title",
+            text: "tutorial.defusing.text",
+            mistake: "tutorial.defusing.mistake",
+            pieces: vec![
+                ((2, 4), GamePiece::new(Blue, Circle, C)),
+                ((3, 4), GamePiece::new(Blue, Square, D)),
+                ((5, 4), GamePiece::new(Yellow, Triangle, G)),
+            ],
+            path: vec![(2, 4), (3, 4), (4, 4), (5, 4)],
+            avoid: vec![],
+            bomb: (4, 4),
+            bomb_points: 80,
+        },
+    ]
+}
+
+/// Progress through the scripted lessons
+#[derive(Resource)]
+pub struct Tutorial {
+    lessons: Vec<Lesson>,
+    lesson: usize,
+    /// The current lesson's line was drawn and is being scored
+    completed: bool,
+    hint: Option<&'static str>,
+}
+
+impl Tutorial {
+    fn new() -> Self {
+        Tutorial {
+            lessons: lessons(),
+            lesson: 0,
+            completed: false,
+            hint: None,
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.lesson = 0;
+        self.completed = false;
+        self.hint = None;
+    }
+
+    /// The lesson being played, or None once every lesson is done
+    pub fn current(&self) -> Option<&Lesson> {
+        self.lessons.get(self.lesson)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.lesson >= self.lessons.len()
+    }
+
+    //Sets up the board and bomb for the current lesson
+    fn load(&self, grid: &mut GameGrid, bomb: &mut MatchBomb) {
+        if let Some(lesson) = self.current() {
+            *grid = lesson.board();
+            bomb.arm(TUTORIAL_TURNS, lesson.bomb_points);
+        }
+    }
+}
+
+//Marker for the lesson instructions
+#[derive(Component)]
+pub struct TutorialDisplay;
+
+//Systems
+
+fn start_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    mut grid: ResMut<GameGrid>,
+    mut bomb: ResMut<MatchBomb>,
+) {
+    info!("Starting tutorial");
+    tutorial.restart();
+    tutorial.load(&mut grid, &mut bomb);
+}
+
+fn setup_tutorial_display(mut commands: Commands, theme: Res<UiTheme>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::VMin(8.0),
+            left: Val::Percent(10.0),
+            width: Val::Percent(80.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(theme.hud_background),
+        NodeRole::Panel,
+        Text::default(),
+        theme.text_style(),
+        TutorialDisplay,
+        HudDisplay,
+        StateScoped(ScreenState::Game),
+    ));
+}
+
+fn update_tutorial_display(
+    mut commands: Commands,
+    display: Single<Entity, With<TutorialDisplay>>,
+    new_display_q: Query<(), Added<TutorialDisplay>>,
+    tutorial: Res<Tutorial>,
+) {
+    //A rebuilt HUD needs filling in even if the lesson didn't change
+    if !tutorial.is_changed() && new_display_q.is_empty() {
+        return;
+    }
+
+    let display_text = match tutorial.current() {
+        Some(lesson) => {
+            let text = LocalizedText::new(if tutorial.hint.is_some() {
+                "tutorial.lesson_with_hint"
+            } else {
+                "tutorial.lesson"
+            })
+            .arg("number", tutorial.lesson + 1)
+            .arg("total", tutorial.lessons.len())
+            .arg_key("title", lesson.title)
+            .arg_key("text", lesson.text);
+            match tutorial.hint {
+                Some(hint) => text.arg_key("hint", hint),
+                None => text,
+            }
+        }
+        None => LocalizedText::new("tutorial.complete"),
+    };
+    commands.entity(*display).insert(display_text);
+}
+
+///Checks the line built by the cell line pipeline against the lesson path
+fn check_step(
+    mut tutorial: ResMut<Tutorial>,
+    mut cell_line: ResMut<CellLine>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut er_line_complete: EventReader<LineCompletedEvent>,
+    mut ew_line_complete: EventWriter<LineCompletedEvent>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+) {
+    let line_ended = er_line_complete.read().count() > 0;
+    if tutorial.completed {
+        return;
+    }
+    let Some(lesson) = tutorial.current() else {
+        return;
+    };
+
+    let on_path = cell_line
+        .visited
+        .iter()
+        .enumerate()
+        .all(|(step, position)| lesson.path_position(step) == Some(*position));
+    let finished = on_path && cell_line.visited.len() == lesson.path.len();
+
+    if finished {
+        cell_line.commit(&mut ew_line_complete);
+        tutorial.completed = true;
+        tutorial.hint = None;
+    } else if !on_path || line_ended {
+        //Dropping the line turns a failed step into a short line instead of popping part of the path
+        let mistake = lesson.mistake;
+        cell_line.clear();
+        next_state.set(GameState::FreePick);
+        tutorial.hint = Some(mistake);
+        ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+            EffectName::Negative,
+            Some(true),
+            None,
+        )));
+    }
+}
+
+///Swaps the regenerated board for the next lesson's board
+fn load_lesson_board(
+    mut tutorial: ResMut<Tutorial>,
+    mut grid: ResMut<GameGrid>,
+    mut bomb: ResMut<MatchBomb>,
+) {
+    if tutorial.completed {
+        tutorial.completed = false;
+        tutorial.lesson += 1;
+        if tutorial.is_finished() {
+            info!("Tutorial finished");
+            return;
+        }
+    }
+    tutorial.load(&mut grid, &mut bomb);
+}
+
+fn replay_tutorial(
+    actions: ActionInput,
+    mut tutorial: ResMut<Tutorial>,
+    mut ew_update_board: EventWriter<UpdateBoardEvent>,
+) {
+    if tutorial.is_finished() && actions.just_pressed(Action::Confirm) {
+        tutorial.restart();
+        ew_update_board.write_default();
+    }
+}
+
+///Outlines the next cell to click, the rest of the path, and cells to stay away from
+fn draw_highlights(
+    mut gizmos: Gizmos,
+    tutorial: Res<Tutorial>,
+    cell_line: Res<CellLine>,
+    time: Res<Time>,
+) {
+    if tutorial.completed {
+        return;
+    }
+    let Some(lesson) = tutorial.current() else {
+        return;
+    };
+
+    let pulse = 0.5 + 0.5 * (time.elapsed_secs() * 4.0).sin();
+    let next = cell_line.visited.len();
+    for step in next..lesson.path.len() {
+        let Some(position) = lesson.path_position(step) else {
+            continue;
+        };
+        let color = if step == next {
+            Color::srgba(0.5, 5.0, 5.0, 0.5 + 0.5 * pulse)
+        } else {
+            Color::srgba(0.5, 2.0, 2.0, 0.4)
+        };
+        gizmos.rect(position.extend(0.0) * 2.0, Vec2::splat(1.8), color);
+    }
+
+    for cell in lesson.avoid.iter() {
+        let position = Vec2::new(cell.0 as f32, cell.1 as f32).extend(0.0) * 2.0;
+        let color = Color::srgba(5.0, 0.2, 0.2, 1.0);
+        gizmos.rect(position, Vec2::splat(1.8), color);
+        gizmos.line(
+            position + Vec3::new(-0.9, -0.9, 0.0),
+            position + Vec3::new(0.9, 0.9, 0.0),
+            color,
+        );
+        gizmos.line(
+            position + Vec3::new(-0.9, 0.9, 0.0),
+            position + Vec3::new(0.9, -0.9, 0.0),
+            color,
+        );
+    }
+}
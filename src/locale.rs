@@ -0,0 +1,267 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{ron_asset::RonAssetLoader, save};
+
+// Every user facing string lives in a per-language table under assets/locale.
+// UI text carries a LocalizedText naming its key, and is rewritten whenever the language changes.
+// Keys missing from a table fall back to English, then to the key itself.
+
+const SAVE_NAME: &str = "language";
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LocaleFile>()
+            .init_asset_loader::<RonAssetLoader<LocaleFile>>()
+            .insert_resource(Locale::default())
+            .insert_resource(save::load::<LanguageSettings>(SAVE_NAME))
+            .add_systems(Startup, load_locales)
+            .add_systems(
+                Update,
+                (
+                    apply_language.run_if(
+                        resource_changed::<LanguageSettings>.or(on_event::<AssetEvent<LocaleFile>>),
+                    ),
+                    localize_text,
+                    save_language.run_if(resource_changed::<LanguageSettings>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The languages shipped in assets/locale
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::Spanish, Language::French];
+
+    pub fn next(&self) -> Self {
+        match self {
+            Language::English => Language::Spanish,
+            Language::Spanish => Language::French,
+            Language::French => Language::English,
+        }
+    }
+
+    fn path(&self) -> &'static str {
+        match self {
+            Language::English => "locale/en.ron",
+            Language::Spanish => "locale/es.ron",
+            Language::French => "locale/fr.ron",
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct LanguageSettings {
+    pub language: Language,
+}
+
+/// Which counts take the singular form
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+pub enum PluralRule {
+    /// Only exactly one, as in English and Spanish
+    #[default]
+    One,
+    /// Zero and one, as in French
+    ZeroOrOne,
+}
+
+impl PluralRule {
+    fn is_singular(&self, count: u64) -> bool {
+        match self {
+            PluralRule::One => count == 1,
+            PluralRule::ZeroOrOne => count <= 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluralForms {
+    one: String,
+    other: String,
+}
+
+/// A string table as written in its RON file
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct LocaleFile {
+    /// The language's name in that language, for the picker
+    name: String,
+    plural: PluralRule,
+    strings: HashMap<String, String>,
+    #[serde(default)]
+    plurals: HashMap<String, PluralForms>,
+}
+
+/// Keeps every table loaded, so switching is instant and each file is watched
+#[derive(Resource)]
+struct LocaleFiles(HashMap<Language, Handle<LocaleFile>>);
+
+/// The strings of the current language, with English filling any gaps
+#[derive(Resource, Debug, Default)]
+pub struct Locale {
+    pub name: String,
+    plural: PluralRule,
+    strings: HashMap<String, String>,
+    plurals: HashMap<String, PluralForms>,
+}
+
+impl Locale {
+    fn from_files(file: &LocaleFile, fallback: Option<&LocaleFile>) -> Self {
+        let mut strings = fallback.map(|f| f.strings.clone()).unwrap_or_default();
+        let mut plurals = fallback.map(|f| f.plurals.clone()).unwrap_or_default();
+        strings.extend(file.strings.clone());
+        plurals.extend(file.plurals.clone());
+        Locale {
+            name: file.name.clone(),
+            plural: file.plural,
+            strings,
+            plurals,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> String {
+        self.strings
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Fills in a localized text, arguments included
+    pub fn render(&self, text: &LocalizedText) -> String {
+        let mut rendered = match text.count {
+            Some(count) => match self.plurals.get(&text.key) {
+                Some(forms) if self.plural.is_singular(count) => forms.one.clone(),
+                Some(forms) => forms.other.clone(),
+                None => self.get(&text.key),
+            }
+            .replace("{n}", &count.to_string()),
+            None => self.get(&text.key),
+        };
+
+        for (name, arg) in text.args.iter() {
+            let value = match arg {
+                LocaleArg::Plain(value) => value.clone(),
+                LocaleArg::Text(inner) => self.render(inner),
+                LocaleArg::List(items) => items
+                    .iter()
+                    .map(|item| self.render(item))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            rendered = rendered.replace(&format!("{{{name}}}"), &value);
+        }
+        rendered
+    }
+}
+
+/// A value filled into a `{name}` placeholder
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocaleArg {
+    /// Shown as is, like numbers
+    Plain(String),
+    /// Localized itself, like an attribute name
+    Text(LocalizedText),
+    /// Localized and joined with commas
+    List(Vec<LocalizedText>),
+}
+
+/// Text drawn from the string tables. Keeps its Text up to date with the current language
+#[derive(Component, Debug, Clone, PartialEq)]
+#[require(Text)]
+pub struct LocalizedText {
+    key: String,
+    count: Option<u64>,
+    args: Vec<(&'static str, LocaleArg)>,
+}
+
+impl LocalizedText {
+    pub fn new(key: impl Into<String>) -> Self {
+        LocalizedText {
+            key: key.into(),
+            count: None,
+            args: Vec::new(),
+        }
+    }
+
+    /// Picks the singular or plural form for the count, and fills it into `{n}`
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, LocaleArg::Plain(value.to_string())));
+        self
+    }
+
+    pub fn arg_text(mut self, name: &'static str, text: LocalizedText) -> Self {
+        self.args.push((name, LocaleArg::Text(text)));
+        self
+    }
+
+    pub fn arg_key(self, name: &'static str, key: impl Into<String>) -> Self {
+        self.arg_text(name, LocalizedText::new(key))
+    }
+
+    pub fn arg_list(mut self, name: &'static str, items: Vec<LocalizedText>) -> Self {
+        self.args.push((name, LocaleArg::List(items)));
+        self
+    }
+}
+
+/// Key for a name that comes from game data, like an attribute or one of its values
+pub fn name_key(category: &str, name: &str) -> String {
+    format!("{category}.{}", name.to_lowercase())
+}
+
+//Systems
+
+fn load_locales(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let files = Language::ALL
+        .into_iter()
+        .map(|language| (language, asset_server.load(language.path())))
+        .collect();
+    commands.insert_resource(LocaleFiles(files));
+}
+
+///Rebuilds the string table when another language is picked, or when a table loads or is edited
+fn apply_language(
+    settings: Res<LanguageSettings>,
+    locale_files: Res<LocaleFiles>,
+    files: Res<Assets<LocaleFile>>,
+    mut locale: ResMut<Locale>,
+) {
+    let get = |language: Language| {
+        locale_files
+            .0
+            .get(&language)
+            .and_then(|handle| files.get(handle))
+    };
+    let Some(file) = get(settings.language) else {
+        return;
+    };
+    *locale = Locale::from_files(file, get(Language::English));
+}
+
+///Rewrites new or changed localized text, and all of it when the language changes
+fn localize_text(locale: Res<Locale>, mut text_q: Query<(Ref<LocalizedText>, &mut Text)>) {
+    for (localized, mut text) in text_q.iter_mut() {
+        if locale.is_changed() || localized.is_changed() {
+            text.0 = locale.render(&localized);
+        }
+    }
+}
+
+fn save_language(settings: Res<LanguageSettings>) {
+    save::save(SAVE_NAME, &*settings);
+}
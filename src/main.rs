@@ -1,5 +1,8 @@
+use crate::achievements::*;
+use crate::actions::*;
 use crate::audio_server::*;
 use crate::camera::*;
+use crate::locale::*;
 use crate::screen::*;
 use crate::ui_theme::*;
 use bevy::asset::AssetMetaCheck;
@@ -10,9 +13,14 @@ use bevy_inspector_egui::bevy_egui::EguiPlugin;
 use bevy_inspector_egui::quick::*;
 use game::GamePlugin;
 
+pub mod achievements;
+pub mod actions;
 pub mod audio_server;
 pub mod camera;
 pub mod game;
+pub mod locale;
+pub mod ron_asset;
+pub mod save;
 pub mod screen;
 pub mod ui_theme;
 fn main() {
@@ -49,7 +57,10 @@ fn main() {
         // })
         // .add_plugins(WorldInspectorPlugin::default())
         .add_plugins(UiThemePlugin)
+        .add_plugins(LocalePlugin)
         .add_plugins(GamePlugin)
+        .add_plugins(AchievementsPlugin)
+        .add_plugins(ActionsPlugin)
         .add_plugins(MeshPickingPlugin)
         .run();
 }
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use serde::{Serialize, de::DeserializeOwned};
+
+// Small persistence layer for progress and settings.
+// Native builds write RON files into the saves directory,
+// web builds keep the same RON strings in local storage.
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIR: &str = "saves";
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_PREFIX: &str = "shape_color_sound_bomb";
+
+/// Loads a saved value, falling back to the default if nothing was saved or it can't be read
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let Some(contents) = read(name) else {
+        return T::default();
+    };
+
+    match ron::from_str(&contents) {
+        Ok(value) => value,
+        Err(error) => {
+            warn!("Could not parse save {name}: {error}");
+            T::default()
+        }
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => write(name, &contents),
+        Err(error) => warn!("Could not serialize save {name}: {error}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(std::path::Path::new(SAVE_DIR).join(format!("{name}.ron"))).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(name: &str, contents: &str) {
+    let dir = std::path::Path::new(SAVE_DIR);
+    if let Err(error) = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(dir.join(format!("{name}.ron")), contents))
+    {
+        warn!("Could not write save {name}: {error}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(name: &str) -> Option<String> {
+    storage()?
+        .get_item(&format!("{STORAGE_PREFIX}.{name}"))
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(name: &str, contents: &str) {
+    if let Some(storage) = storage()
+        && storage
+            .set_item(&format!("{STORAGE_PREFIX}.{name}"), contents)
+            .is_err()
+    {
+        warn!("Could not write save {name}");
+    }
+}
@@ -1,9 +1,13 @@
 use bevy::prelude::*;
 
 use components::*;
+use achievements::AchievementsScreenPlugin;
 use menu::MainMenuPlugin;
 use credits::CreditsPlugin;
 use options::OptionsPlugin;
+use stats::StatsPlugin;
+use focus::FocusPlugin;
+use controls::ControlsPlugin;
 use systems::*;
 
 
@@ -13,6 +17,10 @@ pub mod systems;
 pub mod menu;
 pub mod credits;
 pub mod options;
+pub mod achievements;
+pub mod stats;
+pub mod focus;
+pub mod controls;
 
 pub struct ScreenPlugin;
 
@@ -20,12 +28,17 @@ impl Plugin for ScreenPlugin {
     fn build(&self, app: &mut App) {
         app
         .init_state::<ScreenState>()
+        .enable_state_scoped_entities::<ScreenState>()
         .add_systems(Startup, setup_splash_screen)
         .add_systems(Update, continue_from_splash.run_if(in_state(ScreenState::Splash)))
         .add_systems(OnExit(ScreenState::Splash), deconstruct_splash)
         .add_plugins(MainMenuPlugin)
         .add_plugins(CreditsPlugin)        
         .add_plugins(OptionsPlugin)
+        .add_plugins(AchievementsScreenPlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(FocusPlugin)
+        .add_plugins(ControlsPlugin)
         ;
     }
 }
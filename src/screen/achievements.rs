@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+
+use super::{components::ScreenState, menu::UiRoot};
+use crate::{
+    achievements::{AchievementId, Achievements},
+    actions::{Action, ActionInput},
+    locale::LocalizedText,
+    ui_theme::*,
+};
+
+const LOCKED_TEXT_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+pub struct AchievementsScreenPlugin;
+
+impl Plugin for AchievementsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Achievements), setup_achievements)
+            .add_systems(
+                Update,
+                return_to_menu.run_if(in_state(ScreenState::Achievements)),
+            )
+            .add_systems(
+                OnExit(ScreenState::Achievements),
+                deconstruct_achievements_menu,
+            );
+    }
+}
+
+pub fn setup_achievements(
+    mut commands: Commands,
+    uiroot: Single<Entity, With<UiRoot>>,
+    achievements: Res<Achievements>,
+    theme: Res<UiTheme>,
+) {
+    info!("Constructing achievements gallery");
+
+    let unlocked_count = AchievementId::ALL
+        .iter()
+        .filter(|id| achievements.is_unlocked(**id))
+        .count();
+
+    let title_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            LocalizedText::new("achievements.title")
+                .arg("unlocked", unlocked_count)
+                .arg("total", AchievementId::ALL.len()),
+            theme.text_style(),
+        ))
+        .id();
+    commands.entity(*uiroot).add_child(title_text);
+
+    for id in AchievementId::ALL {
+        let unlocked = achievements.is_unlocked(id);
+        let (border, text_color) = if unlocked {
+            (theme.button_background_hover, theme.text_color)
+        } else {
+            (theme.button_border_normal, LOCKED_TEXT_COLOR)
+        };
+
+        let card = commands
+            .spawn((
+                Node {
+                    width: Val::Px(450.0),
+                    margin: UiRect::all(Val::Px(4.0)),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                BackgroundColor(theme.button_background_normal),
+                BorderColor(border),
+                NodeRole::Card {
+                    highlighted: unlocked,
+                },
+            ))
+            .id();
+
+        let name_text = commands
+            .spawn((
+                if unlocked {
+                    id.name()
+                } else {
+                    LocalizedText::new("achievements.locked").arg_text("name", id.name())
+                },
+                theme.font(),
+                TextColor(text_color),
+            ))
+            .id();
+
+        let description_text = commands
+            .spawn((id.description(), theme.font(), TextColor(text_color)))
+            .id();
+
+        commands
+            .entity(card)
+            .add_children(&[name_text, description_text]);
+        commands.entity(*uiroot).add_child(card);
+    }
+}
+
+pub fn return_to_menu(actions: ActionInput, mut next_state: ResMut<NextState<ScreenState>>) {
+    if actions.just_released(Action::Back) {
+        next_state.set(ScreenState::Menu);
+    }
+}
+
+pub fn deconstruct_achievements_menu(mut commands: Commands, uiroot: Single<Entity, With<UiRoot>>) {
+    info!("Deconstructing achievements gallery");
+    commands.entity(*uiroot).despawn_related::<Children>();
+}
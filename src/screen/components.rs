@@ -8,6 +8,8 @@ pub enum ScreenState {
     Option,
     Game,
     Credits,
+    Achievements,
+    Stats,
     Exit
 }
 
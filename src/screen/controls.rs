@@ -0,0 +1,286 @@
+use bevy::{input::InputSystem, prelude::*, ui::UiSystem};
+
+use super::{components::ScreenState, options::create_incremental_button};
+use crate::{
+    actions::{Action, Binding, Bindings},
+    audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings},
+    locale::LocalizedText,
+    ui_theme::*,
+};
+
+// Controls tab of the options screen.
+// Clicking a binding waits for the next key or gamepad button and binds it in its place.
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Capture::default())
+            .add_systems(
+                PreUpdate,
+                capture_binding
+                    .after(InputSystem)
+                    .before(UiSystem::Focus)
+                    .run_if(in_state(ScreenState::Option)),
+            )
+            .add_systems(
+                Update,
+                (
+                    controls_buttons_system,
+                    refresh_controls
+                        .run_if(resource_changed::<Bindings>.or(resource_changed::<Capture>)),
+                )
+                    .chain()
+                    .run_if(in_state(ScreenState::Option)),
+            )
+            .add_systems(OnExit(ScreenState::Option), stop_capture);
+    }
+}
+
+/// The binding waiting for the next key or button press.
+/// No slot means the press is added to the action's bindings
+#[derive(Resource, Debug, Default)]
+pub struct Capture(Option<(Action, Option<usize>)>);
+
+impl Capture {
+    fn is(&self, action: Action, slot: Option<usize>) -> bool {
+        self.0 == Some((action, slot))
+    }
+}
+
+#[derive(Component, Debug)]
+pub enum ControlsButtonType {
+    Rebind(Action, usize),
+    Add(Action),
+    Reset,
+}
+
+//Marker for the node holding the binding rows
+#[derive(Component)]
+pub struct ControlsPanel;
+
+/// Builds the controls tab under the given parent
+pub fn spawn_controls(
+    commands: &mut Commands,
+    parent: Entity,
+    theme: &Res<UiTheme>,
+    bindings: &Bindings,
+    capture: &Capture,
+) {
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ControlsPanel,
+        ))
+        .id();
+    fill_controls(commands, panel, theme, bindings, capture);
+    commands.entity(parent).add_child(panel);
+}
+
+fn fill_controls(
+    commands: &mut Commands,
+    panel: Entity,
+    theme: &Res<UiTheme>,
+    bindings: &Bindings,
+    capture: &Capture,
+) {
+    for action in Action::ALL {
+        let row = commands
+            .spawn(Node {
+                flex_direction: FlexDirection::Row,
+                margin: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .id();
+
+        let label = commands
+            .spawn((
+                Node {
+                    margin: UiRect::horizontal(Val::Px(5.0)),
+                    width: Val::Px(175.0),
+                    ..default()
+                },
+                action.label(),
+                theme.text_style(),
+            ))
+            .id();
+        commands.entity(row).add_child(label);
+
+        for (slot, binding) in bindings.get(action).iter().enumerate() {
+            let text = if capture.is(action, Some(slot)) {
+                LocalizedText::new("controls.press")
+            } else {
+                LocalizedText::new("controls.binding").arg("binding", binding.label())
+            };
+            let button = create_incremental_button(
+                commands,
+                ControlsButtonType::Rebind(action, slot),
+                text,
+                theme,
+            );
+            commands.entity(row).add_child(button);
+        }
+
+        let add_text = if capture.is(action, None) {
+            LocalizedText::new("controls.press")
+        } else {
+            LocalizedText::new("controls.add")
+        };
+        let add_button = create_incremental_button(
+            commands,
+            ControlsButtonType::Add(action),
+            add_text,
+            theme,
+        );
+        commands.entity(row).add_child(add_button);
+        commands.entity(panel).add_child(row);
+    }
+
+    for (binding, first, second) in bindings.conflicts() {
+        let conflict_text = commands
+            .spawn((
+                LocalizedText::new("controls.conflict")
+                    .arg("binding", binding.label())
+                    .arg_text("first", first.label())
+                    .arg_text("second", second.label()),
+                theme.font(),
+                TextColor(Color::srgb(1.0, 0.3, 0.3)),
+            ))
+            .id();
+        commands.entity(panel).add_child(conflict_text);
+    }
+
+    let help_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            LocalizedText::new("controls.help"),
+            theme.text_style(),
+        ))
+        .id();
+    let reset_button = create_incremental_button(
+        commands,
+        ControlsButtonType::Reset,
+        LocalizedText::new("controls.reset"),
+        theme,
+    );
+    commands
+        .entity(panel)
+        .add_children(&[help_text, reset_button]);
+}
+
+//Systems
+
+fn controls_buttons_system(
+    mut interaction_q: Query<
+        (&Interaction, &mut BackgroundColor, &ControlsButtonType),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut capture: ResMut<Capture>,
+    mut bindings: ResMut<Bindings>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    theme: Res<UiTheme>,
+) {
+    for (interaction, mut bgcolor, button_type) in &mut interaction_q {
+        match *interaction {
+            Interaction::None => *bgcolor = BackgroundColor(theme.button_background_normal),
+            Interaction::Hovered => *bgcolor = BackgroundColor(theme.button_background_hover),
+            Interaction::Pressed => {
+                *bgcolor = BackgroundColor(theme.button_background_pressed);
+                match button_type {
+                    ControlsButtonType::Rebind(action, slot) => {
+                        capture.0 = Some((*action, Some(*slot)));
+                    }
+                    ControlsButtonType::Add(action) => capture.0 = Some((*action, None)),
+                    ControlsButtonType::Reset => {
+                        capture.0 = None;
+                        *bindings = Bindings::defaults();
+                    }
+                }
+                ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+                    EffectName::UiConfirm,
+                    Some(false),
+                    None,
+                )));
+            }
+        }
+    }
+}
+
+///Binds the next key or gamepad button pressed while a binding is waiting.
+///The press is swallowed so it doesn't also act on the screen, like Escape leaving the options.
+///A Back press cancels the wait instead, or Escape while Back has nothing bound
+fn capture_binding(
+    mut capture: ResMut<Capture>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut gamepads: Query<&mut Gamepad>,
+    mut bindings: ResMut<Bindings>,
+) {
+    let Some((action, slot)) = capture.0 else {
+        return;
+    };
+    let pressed = keys
+        .get_just_pressed()
+        .next()
+        .copied()
+        .map(Binding::Key)
+        .or_else(|| {
+            gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next().copied())
+                .map(Binding::Gamepad)
+        });
+    let Some(binding) = pressed else {
+        return;
+    };
+
+    match binding {
+        Binding::Key(key) => keys.reset(key),
+        Binding::Gamepad(button) => {
+            for mut gamepad in gamepads.iter_mut() {
+                gamepad.digital_mut().reset(button);
+            }
+        }
+    }
+    capture.0 = None;
+
+    let back = bindings.get(Action::Back);
+    if back.contains(&binding) || back.is_empty() && binding == Binding::Key(KeyCode::Escape) {
+        return;
+    }
+
+    let remove = binding == Binding::Key(KeyCode::Delete);
+    let action_bindings = bindings.get_mut(action);
+    match slot {
+        Some(slot) if slot < action_bindings.len() => {
+            if remove {
+                action_bindings.remove(slot);
+            } else {
+                action_bindings[slot] = binding;
+            }
+        }
+        None if !remove && !action_bindings.contains(&binding) => action_bindings.push(binding),
+        _ => {}
+    }
+}
+
+fn refresh_controls(
+    mut commands: Commands,
+    panel: Single<Entity, With<ControlsPanel>>,
+    theme: Res<UiTheme>,
+    bindings: Res<Bindings>,
+    capture: Res<Capture>,
+) {
+    commands.entity(*panel).despawn_related::<Children>();
+    fill_controls(&mut commands, *panel, &theme, &bindings, &capture);
+}
+
+fn stop_capture(mut capture: ResMut<Capture>) {
+    capture.0 = None;
+}
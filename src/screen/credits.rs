@@ -1,7 +1,11 @@
 use bevy::prelude::*;
 
 use super::{components::ScreenState, menu::UiRoot};
-use crate::ui_theme::*;
+use crate::{
+    actions::{Action, ActionInput},
+    locale::LocalizedText,
+    ui_theme::*,
+};
 pub struct CreditsPlugin;
 
 impl Plugin for CreditsPlugin {
@@ -21,43 +25,47 @@ pub fn setup_credits(
     theme: Res<UiTheme>,
 ) {
     let title_text = commands
-        .spawn((Node { ..default() }, Text::from("Credits:"), theme.font()))
+        .spawn((
+            Node { ..default() },
+            LocalizedText::new("credits.title"),
+            theme.text_style(),
+        ))
         .id();
 
     let credits_text = commands
         .spawn((
             Node { ..default() },
-            Text::from(" Made by xchino."),
-            theme.font(),
+            LocalizedText::new("credits.made_by"),
+            theme.text_style(),
         ))
         .id();
 
     let bevy_text = commands
         .spawn((
             Node { ..default() },
-            Text::from(" With Bevy Engine."),
-            theme.font(),
+            LocalizedText::new("credits.bevy"),
+            theme.text_style(),
         ))
         .id();
     let flock_text = commands
         .spawn((
             Node { ..default() },
-            Text::from("And various code lifted from community template by TheBevyFlock."),
-            theme.font(),
+            LocalizedText::new("credits.template"),
+            theme.text_style(),
         ))
         .id();
     let font_text = commands
         .spawn((
             Node { ..default() },
-            Text::from(" Using OpenDyselxic font."),
-            theme.font(),
+            LocalizedText::new("credits.font"),
+            theme.text_style(),
         ))
         .id();
     let others_text = commands
         .spawn((
             Node { ..default() },
-            Text::from(" And countless other contributions from the Bevy community and ecosystem."),
-            theme.font(),
+            LocalizedText::new("credits.community"),
+            theme.text_style(),
         ))
         .id();
 
@@ -71,11 +79,8 @@ pub fn setup_credits(
     ]);
 }
 
-pub fn return_to_menu(
-    mut next_state: ResMut<NextState<ScreenState>>,
-    input: ResMut<ButtonInput<KeyCode>>,
-) {
-    if input.just_released(KeyCode::Escape) {
+pub fn return_to_menu(actions: ActionInput, mut next_state: ResMut<NextState<ScreenState>>) {
+    if actions.just_released(Action::Back) {
         next_state.set(ScreenState::Menu);
     }
 }
@@ -0,0 +1,181 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{prelude::*, ui::UiSystem};
+
+use super::components::ScreenState;
+use crate::{
+    actions::{Action, ActionInput},
+    game::{GameState, pause::PauseState},
+    ui_theme::UiTheme,
+};
+
+const STICK_DEADZONE: f32 = 0.5;
+const STICK_REPEAT_SECS: f32 = 0.2;
+
+// Focus and navigation for gamepad and keyboard players, read through the bound actions.
+// Buttons keep being driven by Interaction: the focused button is pressed by
+// setting its Interaction, so every existing button system works unchanged.
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (
+                release_focus_press.before(UiSystem::Focus),
+                press_focused.after(UiSystem::Focus),
+            ),
+        )
+        .add_systems(
+            Update,
+            (navigate_focus, highlight_focus).chain().run_if(
+                not(in_state(ScreenState::Game))
+                    .or(in_state(PauseState::Paused))
+                    .or(in_state(GameState::GameOver)),
+            ),
+        );
+    }
+}
+
+/// The button gamepad input acts on, along with the border it had before it was focused
+#[derive(Component)]
+pub struct Focused(pub BorderColor);
+
+//Marker for a button pressed from the gamepad, released on the next frame
+#[derive(Component)]
+pub struct FocusPress;
+
+/// Which of the eight directions a stick is pushed towards, if it is pushed far enough
+pub fn stick_direction(stick: Vec2) -> IVec2 {
+    if stick.length() < STICK_DEADZONE {
+        return IVec2::ZERO;
+    }
+    let octant = (stick.to_angle() / (TAU / 8.0)).round();
+    Vec2::from_angle(octant * TAU / 8.0).round().as_ivec2()
+}
+
+/// Turns a held stick into steps: one when it is pushed, then repeating while it is held
+#[derive(Debug)]
+pub struct StickRepeat {
+    direction: IVec2,
+    timer: Timer,
+}
+
+impl Default for StickRepeat {
+    fn default() -> Self {
+        StickRepeat {
+            direction: IVec2::ZERO,
+            timer: Timer::from_seconds(STICK_REPEAT_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl StickRepeat {
+    pub fn update(&mut self, stick: Vec2, delta: Duration) -> IVec2 {
+        let direction = stick_direction(stick);
+        if direction != self.direction {
+            self.direction = direction;
+            self.timer.reset();
+            return direction;
+        }
+        if direction != IVec2::ZERO && self.timer.tick(delta).just_finished() {
+            return direction;
+        }
+        IVec2::ZERO
+    }
+}
+
+//Systems
+
+fn set_focus(commands: &mut Commands, entity: Entity, border: &BorderColor) {
+    commands.entity(entity).insert(Focused(*border));
+}
+
+///Moves focus to the nearest visible button in the pushed direction
+fn navigate_focus(
+    mut commands: Commands,
+    actions: ActionInput,
+    gamepads: Query<&Gamepad>,
+    button_q: Query<(Entity, &GlobalTransform, &InheritedVisibility, &BorderColor), With<Button>>,
+    focused_q: Query<(Entity, &Focused, &GlobalTransform)>,
+    mut repeat: Local<StickRepeat>,
+    time: Res<Time>,
+) {
+    let stick: Vec2 = gamepads.iter().map(|gamepad| gamepad.left_stick()).sum();
+    let step = (actions.cursor_step() + repeat.update(stick, time.delta()))
+        .clamp(IVec2::NEG_ONE, IVec2::ONE);
+    let select = actions.just_pressed(Action::Confirm);
+    if step == IVec2::ZERO && !select {
+        return;
+    }
+
+    let visible = button_q
+        .iter()
+        .filter(|(_, _, visibility, _)| visibility.get());
+
+    let Ok((current, focused, transform)) = focused_q.single() else {
+        //Nothing focused yet, start at the top left button
+        if let Some((entity, _, _, border)) = visible.min_by(|a, b| {
+            let (a, b) = (a.1.translation(), b.1.translation());
+            (a.y, a.x).partial_cmp(&(b.y, b.x)).unwrap()
+        }) {
+            set_focus(&mut commands, entity, border);
+        }
+        return;
+    };
+    if step == IVec2::ZERO {
+        return;
+    }
+
+    //UI space grows downwards
+    let direction = Vec2::new(step.x as f32, -step.y as f32).normalize();
+    let origin = transform.translation().truncate();
+    let nearest = visible
+        .filter(|(entity, ..)| *entity != current)
+        .filter_map(|(entity, other, _, border)| {
+            let offset = other.translation().truncate() - origin;
+            let along = offset.dot(direction);
+            let across = offset.perp_dot(direction).abs();
+            (along > 0.0).then_some((entity, border, along + across * 2.0))
+        })
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+    if let Some((entity, border, _)) = nearest {
+        commands
+            .entity(current)
+            .insert(focused.0)
+            .remove::<Focused>();
+        set_focus(&mut commands, entity, border);
+    }
+}
+
+fn highlight_focus(mut focused_q: Query<&mut BorderColor, Added<Focused>>, theme: Res<UiTheme>) {
+    for mut border in focused_q.iter_mut() {
+        *border = BorderColor(theme.button_background_hover);
+    }
+}
+
+///Presses the focused button when Confirm is pressed
+fn press_focused(
+    mut commands: Commands,
+    actions: ActionInput,
+    mut focused_q: Query<(Entity, &mut Interaction), With<Focused>>,
+) {
+    if !actions.just_pressed(Action::Confirm) {
+        return;
+    }
+    for (entity, mut interaction) in focused_q.iter_mut() {
+        *interaction = Interaction::Pressed;
+        commands.entity(entity).insert(FocusPress);
+    }
+}
+
+fn release_focus_press(
+    mut commands: Commands,
+    mut pressed_q: Query<(Entity, &mut Interaction), With<FocusPress>>,
+) {
+    for (entity, mut interaction) in pressed_q.iter_mut() {
+        *interaction = Interaction::None;
+        commands.entity(entity).remove::<FocusPress>();
+    }
+}
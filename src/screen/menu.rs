@@ -1,6 +1,8 @@
 use crate::{
     audio_server::components::{EffectName, PlaySoundEffectEvent, SfxSettings},
-    ui_theme::UiTheme,
+    game::GameMode,
+    locale::LocalizedText,
+    ui_theme::{NodeRole, UiTheme},
 };
 
 use super::components::ScreenState;
@@ -27,7 +29,10 @@ pub struct UiRoot;
 #[derive(Component, Debug)]
 pub enum MenuButtonType {
     NewGame,
+    Tutorial,
     Options,
+    Achievements,
+    Stats,
     Credits,
     Exit,
 }
@@ -37,6 +42,7 @@ fn menu_button_system(
         (Changed<Interaction>, With<Button>),
     >,
     mut next_state: ResMut<NextState<ScreenState>>,
+    mut next_mode: ResMut<NextState<GameMode>>,
     mut event_writer: EventWriter<PlaySoundEffectEvent>,
     mut exit_writer: EventWriter<AppExit>,
     theme: Res<UiTheme>,
@@ -46,8 +52,17 @@ fn menu_button_system(
             Interaction::Pressed => {
                 *bgcolor = theme.button_background_pressed.into();
                 match button_type {
-                    MenuButtonType::NewGame => next_state.set(ScreenState::Game),
+                    MenuButtonType::NewGame => {
+                        next_mode.set(GameMode::Standard);
+                        next_state.set(ScreenState::Game);
+                    }
+                    MenuButtonType::Tutorial => {
+                        next_mode.set(GameMode::Tutorial);
+                        next_state.set(ScreenState::Game);
+                    }
                     MenuButtonType::Options => next_state.set(ScreenState::Option),
+                    MenuButtonType::Achievements => next_state.set(ScreenState::Achievements),
+                    MenuButtonType::Stats => next_state.set(ScreenState::Stats),
                     MenuButtonType::Credits => next_state.set(ScreenState::Credits),
                     MenuButtonType::Exit => {
                         exit_writer.write(AppExit::Success);
@@ -75,9 +90,9 @@ fn menu_button_system(
 }
 
 //returns a node with the Button component designating interactivity
-fn create_button_node(
-    button_type: MenuButtonType,
-    button_text: Text,
+pub fn create_button_node<T: Component>(
+    button_type: T,
+    button_text: impl Bundle,
     commands: &mut Commands,
     theme: &Res<UiTheme>,
 ) -> Entity {
@@ -94,14 +109,15 @@ fn create_button_node(
             ..default()
         },
         Button,
-        BackgroundColor::from(Color::srgb(0.2, 0.1, 0.1)),
-        BorderColor(Color::BLACK),
+        BackgroundColor(theme.button_background_normal),
+        BorderColor(theme.button_border_normal),
+        NodeRole::Button,
         button_type,
     );
     let button = commands.spawn(button_node).id();
     let button_text_node = commands
         .spawn((
-            theme.font(),
+            theme.text_style(),
             button_text,
             Node {
                 margin: UiRect::all(Val::Px(5.0)),
@@ -147,19 +163,37 @@ fn setup_menu(
         .id();
     let game_button = create_button_node(
         MenuButtonType::NewGame,
-        Text::from("New Game"),
+        LocalizedText::new("menu.new_game"),
+        &mut commands,
+        &theme,
+    );
+    let tutorial_button = create_button_node(
+        MenuButtonType::Tutorial,
+        LocalizedText::new("menu.tutorial"),
         &mut commands,
         &theme,
     );
     let options_button = create_button_node(
         MenuButtonType::Options,
-        Text::from("Options"),
+        LocalizedText::new("menu.options"),
+        &mut commands,
+        &theme,
+    );
+    let achievements_button = create_button_node(
+        MenuButtonType::Achievements,
+        LocalizedText::new("menu.achievements"),
+        &mut commands,
+        &theme,
+    );
+    let stats_button = create_button_node(
+        MenuButtonType::Stats,
+        LocalizedText::new("menu.stats"),
         &mut commands,
         &theme,
     );
     let credits_button = create_button_node(
         MenuButtonType::Credits,
-        Text::from("Credits"),
+        LocalizedText::new("menu.credits"),
         &mut commands,
         &theme,
     );
@@ -167,7 +201,7 @@ fn setup_menu(
     #[cfg(not(target_arch = "wasm32"))]
     let exit_button = create_button_node(
         MenuButtonType::Exit,
-        Text::from("Exit"),
+        LocalizedText::new("menu.exit"),
         &mut commands,
         &theme,
     );
@@ -179,7 +213,10 @@ fn setup_menu(
     commands.entity(*parent_node).add_children(&[
         title_card,
         game_button,
+        tutorial_button,
         options_button,
+        achievements_button,
+        stats_button,
         credits_button,
         exit_button,
     ]);
@@ -1,22 +1,99 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, ui::RelativeCursorPosition};
 
-use crate::audio_server::components::{
-    AudioSettings, EffectName, MasterVolumeChangedEvent, MusicVolumeChangedEvent,
-    PlaySoundEffectEvent, SfxSettings,
+use crate::{
+    actions::{Action, ActionInput, Bindings},
+    audio_server::{
+        components::{AudioBus, AudioSettings, EffectName, PlaySoundEffectEvent, SfxSettings},
+        synth::NoteSettings,
+    },
+    game::{cell_line::ChainRule, palette::PaletteSettings, pause::PauseState},
+    locale::{LanguageSettings, Locale, LocalizedText},
 };
 
-use super::{components::ScreenState, menu::UiRoot};
+use super::{
+    components::ScreenState,
+    controls::{Capture, spawn_controls},
+    menu::{UiRoot, create_button_node},
+};
 use crate::ui_theme::*;
 
 #[derive(Component)]
 pub enum OptionButtonType {
-    MasterVolUp,
-    MasterVolDown,
-    SfxVolUp,
-    SfxVolDown,
-    MusicVolUp,
-    MusicVolDown,
+    VolumeUp(AudioBus),
+    VolumeDown(AudioBus),
+    MuteToggle(AudioBus),
+    InstrumentNext,
+    OctaveUp,
+    OctaveDown,
+    AccidentalNext,
+    ChainRuleToggle,
+    PaletteNext,
+    PatternsToggle,
+    ThemeNext,
+    UiScaleUp,
+    UiScaleDown,
+    LanguageNext,
+}
+
+/// The tabs along the top of the options screen
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsTab {
+    General,
+    Controls,
 }
+
+//Marker for the node holding the contents of the open tab
+#[derive(Component)]
+pub struct OptionsContent;
+
+/// The track of a volume slider. Clicking or dragging along it sets the volume
+#[derive(Component)]
+pub struct VolumeSlider(AudioBus);
+
+//Marker for the filled part of a volume slider
+#[derive(Component)]
+pub struct VolumeSliderFill(AudioBus);
+
+//Marker for the text showing a volume as a percentage
+#[derive(Component)]
+pub struct VolumeText(AudioBus);
+
+//Marker for the text showing the instrument piece notes are played on
+#[derive(Component)]
+pub struct InstrumentText;
+
+//Marker for the text showing the octave piece notes are played in
+#[derive(Component)]
+pub struct OctaveText;
+
+//Marker for the text showing whether piece notes are sharpened or flattened
+#[derive(Component)]
+pub struct AccidentalText;
+
+//Marker for the text showing the current chain rule
+#[derive(Component)]
+pub struct ChainRuleText;
+
+//Marker for the text showing the current color palette
+#[derive(Component)]
+pub struct PaletteText;
+
+//Marker for the text showing whether color patterns are on
+#[derive(Component)]
+pub struct PatternsText;
+
+//Marker for the text showing the current UI theme
+#[derive(Component)]
+pub struct ThemeText;
+
+//Marker for the text showing the UI scale
+#[derive(Component)]
+pub struct UiScaleText;
+
+//Marker for the text showing the current language
+#[derive(Component)]
+pub struct LanguageText;
+
 pub struct OptionsPlugin;
 
 impl Plugin for OptionsPlugin {
@@ -24,27 +101,38 @@ impl Plugin for OptionsPlugin {
         app.add_systems(OnEnter(ScreenState::Option), setup_options)
             .add_systems(
                 Update,
-                option_buttons_system.run_if(in_state(ScreenState::Option)),
+                (
+                    option_buttons_system,
+                    volume_slider_system,
+                    update_volume_sliders,
+                    update_note_text,
+                    update_chain_rule_text,
+                    update_palette_text,
+                    update_theme_text,
+                    update_ui_scale_text,
+                    update_language_text,
+                )
+                    .run_if(in_state(ScreenState::Option).or(in_state(PauseState::Paused))),
+            )
+            .add_systems(
+                Update,
+                (return_to_menu, options_tab_system).run_if(in_state(ScreenState::Option)),
             )
-            .add_systems(Update, return_to_menu.run_if(in_state(ScreenState::Option)))
             .add_systems(OnExit(ScreenState::Option), deconstruct_options_menu);
     }
 }
 
-pub fn return_to_menu(
-    input: ResMut<ButtonInput<KeyCode>>,
-    mut next_state: ResMut<NextState<ScreenState>>,
-) {
-    if input.just_released(KeyCode::Escape) {
+pub fn return_to_menu(actions: ActionInput, mut next_state: ResMut<NextState<ScreenState>>) {
+    if actions.just_released(Action::Back) {
         next_state.set(ScreenState::Menu);
     }
 }
 
 //This returns the entity id of an "incremental" button
-pub fn create_incremental_button(
+pub fn create_incremental_button<T: Component>(
     commands: &mut Commands,
-    button_type: OptionButtonType,
-    text: String,
+    button_type: T,
+    text: impl Bundle,
     theme: &Res<UiTheme>,
 ) -> Entity {
     let button = commands
@@ -58,6 +146,7 @@ pub fn create_incremental_button(
             button_type,
             BackgroundColor(theme.button_background_normal),
             BorderColor(theme.button_border_normal),
+            NodeRole::Button,
         ))
         .id();
 
@@ -67,8 +156,8 @@ pub fn create_incremental_button(
                 margin: UiRect::all(Val::Px(1.0)),
                 ..default()
             },
-            Text::from(text),
-            theme.font(),
+            text,
+            theme.text_style(),
         ))
         .id();
 
@@ -76,14 +165,196 @@ pub fn create_incremental_button(
     button
 }
 
+fn bus_label_key(bus: AudioBus) -> &'static str {
+    match bus {
+        AudioBus::Master => "options.volume.master",
+        AudioBus::Music => "options.volume.music",
+        AudioBus::Sfx => "options.volume.effects",
+        AudioBus::Ui => "options.volume.ui",
+        AudioBus::Notes => "options.volume.notes",
+    }
+}
+
+///Builds a row with a volume slider flanked by step buttons, a percentage readout and a mute toggle.
+///The buttons are what gamepad focus lands on, the track is for the mouse
+fn create_volume_row(
+    commands: &mut Commands,
+    bus: AudioBus,
+    audio_settings: &AudioSettings,
+    theme: &Res<UiTheme>,
+) -> Entity {
+    let row = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id();
+
+    let label = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(100.0),
+                ..default()
+            },
+            LocalizedText::new(bus_label_key(bus)),
+            theme.text_style(),
+        ))
+        .id();
+
+    let down_button = create_incremental_button(
+        commands,
+        OptionButtonType::VolumeDown(bus),
+        Text::from("-"),
+        theme,
+    );
+
+    let track = commands
+        .spawn((
+            Node {
+                margin: UiRect::horizontal(Val::Px(5.0)),
+                width: Val::Px(150.0),
+                height: Val::Px(12.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            Interaction::default(),
+            RelativeCursorPosition::default(),
+            VolumeSlider(bus),
+            BackgroundColor(theme.button_background_normal),
+            BorderColor(theme.button_border_normal),
+            NodeRole::Button,
+        ))
+        .id();
+
+    let fill = commands
+        .spawn((
+            Node {
+                width: Val::Percent(audio_settings.volume(bus) * 100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(theme.text_color),
+            NodeRole::Fill,
+            VolumeSliderFill(bus),
+        ))
+        .id();
+    commands.entity(track).add_child(fill);
+
+    let up_button = create_incremental_button(
+        commands,
+        OptionButtonType::VolumeUp(bus),
+        Text::from("+"),
+        theme,
+    );
+
+    let readout = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(70.0),
+                ..default()
+            },
+            volume_percent_label(audio_settings, bus),
+            theme.text_style(),
+            VolumeText(bus),
+        ))
+        .id();
+
+    let mute_button = create_incremental_button(
+        commands,
+        OptionButtonType::MuteToggle(bus),
+        LocalizedText::new("options.mute"),
+        theme,
+    );
+
+    commands.entity(row).add_children(&[
+        label,
+        down_button,
+        track,
+        up_button,
+        readout,
+        mute_button,
+    ]);
+    row
+}
+
 pub fn setup_options(
     uiroot: Single<Entity, With<UiRoot>>,
     mut commands: Commands,
     theme: Res<UiTheme>,
+    chain_rule: Res<ChainRule>,
+    palette: Res<PaletteSettings>,
+    ui_scale: Res<UiScaleSettings>,
+    audio_settings: Res<AudioSettings>,
+    note_settings: Res<NoteSettings>,
 ) {
     info!("Constructing options menu");
+    let tab_row = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        })
+        .id();
+    let general_tab = create_button_node(
+        OptionsTab::General,
+        LocalizedText::new("options.tab.general"),
+        &mut commands,
+        &theme,
+    );
+    let controls_tab = create_button_node(
+        OptionsTab::Controls,
+        LocalizedText::new("options.tab.controls"),
+        &mut commands,
+        &theme,
+    );
+    commands
+        .entity(tab_row)
+        .add_children(&[general_tab, controls_tab]);
 
-    let master_row_container = commands
+    let content = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            OptionsContent,
+        ))
+        .id();
+    spawn_options(
+        &mut commands,
+        content,
+        &theme,
+        &chain_rule,
+        &palette,
+        &ui_scale,
+        &audio_settings,
+        &note_settings,
+    );
+    commands.entity(*uiroot).add_children(&[tab_row, content]);
+    info!("Setting up options menu");
+}
+
+/// Builds the option rows under the given parent, so the pause menu can reuse them
+pub fn spawn_options(
+    commands: &mut Commands,
+    parent: Entity,
+    theme: &Res<UiTheme>,
+    chain_rule: &ChainRule,
+    palette: &PaletteSettings,
+    ui_scale: &UiScaleSettings,
+    audio_settings: &AudioSettings,
+    note_settings: &NoteSettings,
+) {
+    let volume_rows: Vec<Entity> = AudioBus::ALL
+        .into_iter()
+        .map(|bus| create_volume_row(commands, bus, audio_settings, theme))
+        .collect();
+
+    let instrument_row_container = commands
         .spawn(Node {
             flex_direction: FlexDirection::Row,
             margin: UiRect::all(Val::Px(5.0)),
@@ -92,18 +363,92 @@ pub fn setup_options(
         })
         .id();
 
-    let effect_row_container = commands
+    let instrument_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(175.0),
+                ..default()
+            },
+            instrument_label(note_settings),
+            theme.text_style(),
+            InstrumentText,
+        ))
+        .id();
+
+    let instrument_button = create_incremental_button(
+        commands,
+        OptionButtonType::InstrumentNext,
+        LocalizedText::new("options.next"),
+        theme,
+    );
+
+    let octave_row_container = commands
         .spawn(Node {
             flex_direction: FlexDirection::Row,
             margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
             ..default()
         })
         .id();
 
-    let music_row_container = commands
+    let octave_down_button = create_incremental_button(
+        commands,
+        OptionButtonType::OctaveDown,
+        Text::from("-"),
+        theme,
+    );
+
+    let octave_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(175.0),
+                ..default()
+            },
+            octave_label(note_settings),
+            theme.text_style(),
+            OctaveText,
+        ))
+        .id();
+
+    let octave_up_button =
+        create_incremental_button(commands, OptionButtonType::OctaveUp, Text::from("+"), theme);
+
+    let accidental_row_container = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id();
+
+    let accidental_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(175.0),
+                ..default()
+            },
+            accidental_label(note_settings),
+            theme.text_style(),
+            AccidentalText,
+        ))
+        .id();
+
+    let accidental_button = create_incremental_button(
+        commands,
+        OptionButtonType::AccidentalNext,
+        LocalizedText::new("options.next"),
+        theme,
+    );
+
+    let rules_row_container = commands
         .spawn(Node {
             flex_direction: FlexDirection::Row,
             margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
             ..default()
         })
         .id();
@@ -113,120 +458,276 @@ pub fn setup_options(
             Node {
                 ..Default::default()
             },
-            Text::from("Volume"),
-            theme.font(),
+            LocalizedText::new("options.volume"),
+            theme.text_style(),
         ))
         .id();
 
-    //Create the button entities
-    let master_volup_button = create_incremental_button(
-        &mut commands,
-        OptionButtonType::MasterVolUp,
-        String::from("+"),
-        &theme,
-    );
-    let master_voldown_button = create_incremental_button(
-        &mut commands,
-        OptionButtonType::MasterVolDown,
-        String::from("-"),
-        &theme,
-    );
+    let rules_label = commands
+        .spawn((
+            Node {
+                ..Default::default()
+            },
+            LocalizedText::new("options.rules"),
+            theme.text_style(),
+        ))
+        .id();
 
-    let sfx_volup_button = create_incremental_button(
-        &mut commands,
-        OptionButtonType::SfxVolUp,
-        String::from("+"),
-        &theme,
-    );
+    let chain_rule_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(175.0),
+                ..default()
+            },
+            chain_rule_label(chain_rule),
+            theme.text_style(),
+            ChainRuleText,
+        ))
+        .id();
 
-    let sfx_voldown_button = create_incremental_button(
-        &mut commands,
-        OptionButtonType::SfxVolDown,
-        String::from("-"),
-        &theme,
+    let chain_rule_button = create_incremental_button(
+        commands,
+        OptionButtonType::ChainRuleToggle,
+        LocalizedText::new("options.toggle"),
+        theme,
     );
 
-    let music_volup_button = create_incremental_button(
-        &mut commands,
-        OptionButtonType::MusicVolUp,
-        String::from("+"),
-        &theme,
+    let colors_label = commands
+        .spawn((
+            Node {
+                ..Default::default()
+            },
+            LocalizedText::new("options.colors"),
+            theme.text_style(),
+        ))
+        .id();
+
+    let palette_row_container = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id();
+
+    let patterns_row_container = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id();
+
+    let palette_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(175.0),
+                ..default()
+            },
+            palette_label(palette),
+            theme.text_style(),
+            PaletteText,
+        ))
+        .id();
+
+    let palette_button = create_incremental_button(
+        commands,
+        OptionButtonType::PaletteNext,
+        LocalizedText::new("options.next"),
+        theme,
     );
-    let music_voldown_button = create_incremental_button(
-        &mut commands,
-        OptionButtonType::MusicVolDown,
-        String::from("-"),
-        &theme,
+
+    let patterns_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                width: Val::Px(175.0),
+                ..default()
+            },
+            patterns_label(palette),
+            theme.text_style(),
+            PatternsText,
+        ))
+        .id();
+
+    let patterns_button = create_incremental_button(
+        commands,
+        OptionButtonType::PatternsToggle,
+        LocalizedText::new("options.toggle"),
+        theme,
     );
 
-    //Create the label entities
-    let master_vol_text = commands
+    let interface_label = commands
+        .spawn((
+            Node {
+                ..Default::default()
+            },
+            LocalizedText::new("options.interface"),
+            theme.text_style(),
+        ))
+        .id();
+
+    let theme_row_container = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id();
+
+    let theme_text = commands
         .spawn((
             Node {
                 margin: UiRect::all(Val::Px(5.0)),
                 width: Val::Px(175.0),
                 ..default()
             },
-            Text::from("Main"),
-            theme.font(),
-            BackgroundColor(Color::BLACK),
+            theme_label(theme),
+            theme.text_style(),
+            ThemeText,
         ))
         .id();
 
-    let effect_vol_text = commands
+    let theme_button = create_incremental_button(
+        commands,
+        OptionButtonType::ThemeNext,
+        LocalizedText::new("options.next"),
+        theme,
+    );
+
+    let scale_row_container = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id();
+
+    let scale_down_button = create_incremental_button(
+        commands,
+        OptionButtonType::UiScaleDown,
+        Text::from("-"),
+        theme,
+    );
+
+    let scale_text = commands
         .spawn((
             Node {
                 margin: UiRect::all(Val::Px(5.0)),
                 width: Val::Px(175.0),
                 ..default()
             },
-            Text::from("Effects"),
-            theme.font(),
+            ui_scale_label(ui_scale),
+            theme.text_style(),
+            UiScaleText,
         ))
         .id();
 
-    let music_vol_text = commands
+    let scale_up_button = create_incremental_button(
+        commands,
+        OptionButtonType::UiScaleUp,
+        Text::from("+"),
+        theme,
+    );
+
+    let language_row_container = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            margin: UiRect::all(Val::Px(5.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .id();
+
+    //Filled in by update_language_text, which knows the language's own name
+    let language_text = commands
         .spawn((
             Node {
                 margin: UiRect::all(Val::Px(5.0)),
                 width: Val::Px(175.0),
                 ..default()
             },
-            Text::from("Music"),
-            theme.font(),
+            LocalizedText::new("options.language"),
+            theme.text_style(),
+            LanguageText,
         ))
         .id();
 
+    let language_button = create_incremental_button(
+        commands,
+        OptionButtonType::LanguageNext,
+        LocalizedText::new("options.next"),
+        theme,
+    );
+
     //Add children to parent containers
-    commands.entity(master_row_container).add_children(&[
-        sfx_voldown_button,
-        effect_vol_text,
-        sfx_volup_button,
-    ]);
+    commands
+        .entity(instrument_row_container)
+        .add_children(&[instrument_text, instrument_button]);
 
-    commands.entity(effect_row_container).add_children(&[
-        master_voldown_button,
-        master_vol_text,
-        master_volup_button,
+    commands.entity(octave_row_container).add_children(&[
+        octave_down_button,
+        octave_text,
+        octave_up_button,
     ]);
 
-    commands.entity(music_row_container).add_children(&[
-        music_voldown_button,
-        music_vol_text,
-        music_volup_button,
+    commands
+        .entity(accidental_row_container)
+        .add_children(&[accidental_text, accidental_button]);
+
+    commands
+        .entity(rules_row_container)
+        .add_children(&[chain_rule_text, chain_rule_button]);
+
+    commands
+        .entity(palette_row_container)
+        .add_children(&[palette_text, palette_button]);
+
+    commands
+        .entity(patterns_row_container)
+        .add_children(&[patterns_text, patterns_button]);
+
+    commands
+        .entity(theme_row_container)
+        .add_children(&[theme_text, theme_button]);
+
+    commands.entity(scale_row_container).add_children(&[
+        scale_down_button,
+        scale_text,
+        scale_up_button,
     ]);
 
-    //Add containers to the uiroot
-    commands.entity(*uiroot).add_children(&[
-        volume_label,
-        master_row_container,
-        music_row_container,
-        effect_row_container,
+    commands
+        .entity(language_row_container)
+        .add_children(&[language_text, language_button]);
+
+    //Add containers to the parent
+    commands
+        .entity(parent)
+        .add_child(volume_label)
+        .add_children(&volume_rows);
+    commands.entity(parent).add_children(&[
+        instrument_row_container,
+        octave_row_container,
+        accidental_row_container,
+        rules_label,
+        rules_row_container,
+        colors_label,
+        palette_row_container,
+        patterns_row_container,
+        interface_label,
+        theme_row_container,
+        scale_row_container,
+        language_row_container,
     ]);
-    info!("Setting up options menu");
 }
 
-//#TODO - just send events and handle volume in the audio server
 pub fn option_buttons_system(
     mut interaction_q: Query<
         (&mut Interaction, &mut BackgroundColor, &OptionButtonType),
@@ -234,8 +735,12 @@ pub fn option_buttons_system(
     >,
     mut audio_settings: ResMut<AudioSettings>,
     mut sfx_event_writer: EventWriter<PlaySoundEffectEvent>,
-    mut music_event_writer: EventWriter<MusicVolumeChangedEvent>,
-    mut master_event_writer: EventWriter<MasterVolumeChangedEvent>,
+    mut chain_rule: ResMut<ChainRule>,
+    mut palette: ResMut<PaletteSettings>,
+    mut theme_settings: ResMut<ThemeSettings>,
+    mut ui_scale: ResMut<UiScaleSettings>,
+    mut language: ResMut<LanguageSettings>,
+    mut note_settings: ResMut<NoteSettings>,
     theme: Res<UiTheme>,
 ) {
     for (interaction, mut bgcolor, buttontype) in &mut interaction_q {
@@ -245,42 +750,112 @@ pub fn option_buttons_system(
             Interaction::Pressed => {
                 *bgcolor = BackgroundColor(theme.button_background_pressed);
                 match buttontype {
-                    OptionButtonType::MusicVolUp => {
-                        music_event_writer.write(MusicVolumeChangedEvent(true));
+                    OptionButtonType::VolumeUp(bus) => {
+                        audio_settings.nudge(*bus, true);
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
                     }
-                    OptionButtonType::MusicVolDown => {
-                        music_event_writer.write(MusicVolumeChangedEvent(false));
+                    OptionButtonType::VolumeDown(bus) => {
+                        audio_settings.nudge(*bus, false);
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
                     }
-
-                    OptionButtonType::MasterVolDown => {
-                        master_event_writer.write(MasterVolumeChangedEvent(false));
+                    OptionButtonType::MuteToggle(bus) => {
+                        audio_settings.toggle_mute(*bus);
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::InstrumentNext => {
+                        note_settings.instrument = note_settings.instrument.next();
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::OctaveUp => {
+                        note_settings.raise_octave();
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::OctaveDown => {
+                        note_settings.lower_octave();
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::AccidentalNext => {
+                        note_settings.accidental = note_settings.accidental.next();
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::ChainRuleToggle => {
+                        *chain_rule = chain_rule.toggled();
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::PaletteNext => {
+                        palette.palette = palette.palette.next();
                         sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
                             EffectName::UiConfirm,
                             Some(false),
                             None,
                         )));
                     }
-                    OptionButtonType::MasterVolUp => {
-                        master_event_writer.write(MasterVolumeChangedEvent(true));
+                    OptionButtonType::PatternsToggle => {
+                        palette.patterns = !palette.patterns;
                         sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
                             EffectName::UiConfirm,
                             Some(false),
                             None,
                         )));
                     }
-                    OptionButtonType::SfxVolDown => {
-                        if audio_settings.sfx_vol - 0.1 >= 0.0 {
-                            //Don't allow the volume to go into negatives
-                            audio_settings.sfx_vol -= 0.1;
-                        }
+                    OptionButtonType::ThemeNext => {
+                        theme_settings.theme = theme_settings.theme.next();
                         sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
                             EffectName::UiConfirm,
                             Some(false),
                             None,
                         )));
                     }
-                    OptionButtonType::SfxVolUp => {
-                        audio_settings.sfx_vol += 0.1;
+                    OptionButtonType::UiScaleUp => {
+                        ui_scale.increase();
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::UiScaleDown => {
+                        ui_scale.decrease();
+                        sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                            EffectName::UiConfirm,
+                            Some(false),
+                            None,
+                        )));
+                    }
+                    OptionButtonType::LanguageNext => {
+                        language.language = language.language.next();
                         sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
                             EffectName::UiConfirm,
                             Some(false),
@@ -293,6 +868,235 @@ pub fn option_buttons_system(
     }
 }
 
+///Swaps the contents below the tabs for the tab that was picked
+pub fn options_tab_system(
+    mut commands: Commands,
+    mut interaction_q: Query<
+        (&Interaction, &mut BackgroundColor, &OptionsTab),
+        (Changed<Interaction>, With<Button>),
+    >,
+    content: Single<Entity, With<OptionsContent>>,
+    chain_rule: Res<ChainRule>,
+    palette: Res<PaletteSettings>,
+    ui_scale: Res<UiScaleSettings>,
+    audio_settings: Res<AudioSettings>,
+    note_settings: Res<NoteSettings>,
+    bindings: Res<Bindings>,
+    capture: Res<Capture>,
+    mut ew_sfx_player: EventWriter<PlaySoundEffectEvent>,
+    theme: Res<UiTheme>,
+) {
+    for (interaction, mut bgcolor, tab) in &mut interaction_q {
+        match *interaction {
+            Interaction::None => *bgcolor = BackgroundColor(theme.button_background_normal),
+            Interaction::Hovered => *bgcolor = BackgroundColor(theme.button_background_hover),
+            Interaction::Pressed => {
+                *bgcolor = BackgroundColor(theme.button_background_pressed);
+                commands.entity(*content).despawn_related::<Children>();
+                match tab {
+                    OptionsTab::General => spawn_options(
+                        &mut commands,
+                        *content,
+                        &theme,
+                        &chain_rule,
+                        &palette,
+                        &ui_scale,
+                        &audio_settings,
+                        &note_settings,
+                    ),
+                    OptionsTab::Controls => {
+                        spawn_controls(&mut commands, *content, &theme, &bindings, &capture)
+                    }
+                }
+                ew_sfx_player.write(PlaySoundEffectEvent(SfxSettings::new(
+                    EffectName::UiConfirm,
+                    Some(false),
+                    None,
+                )));
+            }
+        }
+    }
+}
+
+///Sets the volume from where the track was clicked, following the pointer while it is held
+pub fn volume_slider_system(
+    slider_q: Query<(Ref<Interaction>, &RelativeCursorPosition, &VolumeSlider)>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut sfx_event_writer: EventWriter<PlaySoundEffectEvent>,
+) {
+    for (interaction, cursor, slider) in slider_q.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(position) = cursor.normalized else {
+            continue;
+        };
+        let volume = position.x.clamp(0.0, 1.0);
+        //Only write when it moved, so holding still doesn't reapply the volumes every frame
+        if audio_settings.volume(slider.0) != volume {
+            audio_settings.set_volume(slider.0, volume);
+        }
+        if interaction.is_changed() {
+            sfx_event_writer.write(PlaySoundEffectEvent(SfxSettings::new(
+                EffectName::UiConfirm,
+                Some(false),
+                None,
+            )));
+        }
+    }
+}
+
+fn volume_percent_label(audio_settings: &AudioSettings, bus: AudioBus) -> LocalizedText {
+    if audio_settings.muted(bus) {
+        LocalizedText::new("options.muted")
+    } else {
+        LocalizedText::new("options.percent").arg("percent", audio_settings.percent(bus))
+    }
+}
+
+pub fn update_volume_sliders(
+    audio_settings: Res<AudioSettings>,
+    mut fill_q: Query<(&mut Node, &VolumeSliderFill)>,
+    mut text_q: Query<(&mut LocalizedText, &VolumeText)>,
+) {
+    if audio_settings.is_changed() {
+        for (mut node, fill) in fill_q.iter_mut() {
+            node.width = Val::Percent(audio_settings.volume(fill.0) * 100.0);
+        }
+        for (mut text, volume_text) in text_q.iter_mut() {
+            *text = volume_percent_label(&audio_settings, volume_text.0);
+        }
+    }
+}
+
+fn instrument_label(note_settings: &NoteSettings) -> LocalizedText {
+    LocalizedText::new("options.instrument")
+        .arg_key("instrument", note_settings.instrument.label_key())
+}
+
+fn octave_label(note_settings: &NoteSettings) -> LocalizedText {
+    LocalizedText::new("options.octave").arg("octave", note_settings.octave())
+}
+
+fn accidental_label(note_settings: &NoteSettings) -> LocalizedText {
+    LocalizedText::new("options.accidental")
+        .arg_key("accidental", note_settings.accidental.label_key())
+}
+
+pub fn update_note_text(
+    note_settings: Res<NoteSettings>,
+    mut instrument_text_q: Query<
+        &mut LocalizedText,
+        (
+            With<InstrumentText>,
+            Without<OctaveText>,
+            Without<AccidentalText>,
+        ),
+    >,
+    mut octave_text_q: Query<&mut LocalizedText, (With<OctaveText>, Without<AccidentalText>)>,
+    mut accidental_text_q: Query<&mut LocalizedText, With<AccidentalText>>,
+) {
+    if note_settings.is_changed() {
+        for mut text in instrument_text_q.iter_mut() {
+            *text = instrument_label(&note_settings);
+        }
+        for mut text in octave_text_q.iter_mut() {
+            *text = octave_label(&note_settings);
+        }
+        for mut text in accidental_text_q.iter_mut() {
+            *text = accidental_label(&note_settings);
+        }
+    }
+}
+
+pub fn update_chain_rule_text(
+    chain_rule: Res<ChainRule>,
+    mut text_q: Query<&mut LocalizedText, With<ChainRuleText>>,
+) {
+    if chain_rule.is_changed() {
+        for mut text in text_q.iter_mut() {
+            *text = chain_rule_label(&chain_rule);
+        }
+    }
+}
+
+fn chain_rule_label(chain_rule: &ChainRule) -> LocalizedText {
+    LocalizedText::new("options.chain").arg_key("rule", chain_rule.label_key())
+}
+
+fn palette_label(palette: &PaletteSettings) -> LocalizedText {
+    LocalizedText::new("options.palette").arg_key("palette", palette.palette.label_key())
+}
+
+fn patterns_label(palette: &PaletteSettings) -> LocalizedText {
+    LocalizedText::new("options.patterns").arg_key(
+        "state",
+        if palette.patterns {
+            "options.on"
+        } else {
+            "options.off"
+        },
+    )
+}
+
+pub fn update_palette_text(
+    palette: Res<PaletteSettings>,
+    mut palette_text_q: Query<&mut LocalizedText, (With<PaletteText>, Without<PatternsText>)>,
+    mut patterns_text_q: Query<&mut LocalizedText, With<PatternsText>>,
+) {
+    if palette.is_changed() {
+        for mut text in palette_text_q.iter_mut() {
+            *text = palette_label(&palette);
+        }
+        for mut text in patterns_text_q.iter_mut() {
+            *text = patterns_label(&palette);
+        }
+    }
+}
+
+fn theme_label(theme: &UiTheme) -> LocalizedText {
+    LocalizedText::new("options.theme").arg("theme", &theme.name)
+}
+
+///Follows the theme that was applied, which can lag the pick while its file loads
+pub fn update_theme_text(
+    theme: Res<UiTheme>,
+    mut text_q: Query<&mut LocalizedText, With<ThemeText>>,
+) {
+    if theme.is_changed() {
+        for mut text in text_q.iter_mut() {
+            *text = theme_label(&theme);
+        }
+    }
+}
+
+fn ui_scale_label(ui_scale: &UiScaleSettings) -> LocalizedText {
+    LocalizedText::new("options.ui_scale").arg("percent", ui_scale.percent())
+}
+
+pub fn update_ui_scale_text(
+    ui_scale: Res<UiScaleSettings>,
+    mut text_q: Query<&mut LocalizedText, With<UiScaleText>>,
+) {
+    if ui_scale.is_changed() {
+        for mut text in text_q.iter_mut() {
+            *text = ui_scale_label(&ui_scale);
+        }
+    }
+}
+
+pub fn update_language_text(
+    locale: Res<Locale>,
+    new_text_q: Query<(), Added<LanguageText>>,
+    mut text_q: Query<&mut LocalizedText, With<LanguageText>>,
+) {
+    if locale.is_changed() || !new_text_q.is_empty() {
+        for mut text in text_q.iter_mut() {
+            *text = LocalizedText::new("options.language").arg("language", &locale.name);
+        }
+    }
+}
+
 pub fn deconstruct_options_menu(mut commands: Commands, uiroot: Single<Entity, With<UiRoot>>) {
     info!("Deconstruct Options Menu");
     commands.entity(*uiroot).despawn_related::<Children>();
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use super::{components::ScreenState, menu::UiRoot};
+use crate::{
+    actions::{Action, ActionInput},
+    game::{attributes::AttributeRegistry, statistics::Statistics},
+    locale::LocalizedText,
+    ui_theme::*,
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Stats), setup_stats)
+            .add_systems(Update, return_to_menu.run_if(in_state(ScreenState::Stats)))
+            .add_systems(OnExit(ScreenState::Stats), deconstruct_stats_menu);
+    }
+}
+
+pub fn setup_stats(
+    mut commands: Commands,
+    uiroot: Single<Entity, With<UiRoot>>,
+    statistics: Res<Statistics>,
+    registry: Res<AttributeRegistry>,
+    theme: Res<UiTheme>,
+) {
+    info!("Constructing stats screen");
+
+    let title_text = commands
+        .spawn((
+            Node {
+                margin: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            LocalizedText::new("stats.lifetime_title"),
+            theme.text_style(),
+        ))
+        .id();
+    commands.entity(*uiroot).add_child(title_text);
+
+    for line in statistics.lifetime.summary(&registry) {
+        let line_text = commands
+            .spawn((Node { ..default() }, line, theme.text_style()))
+            .id();
+        commands.entity(*uiroot).add_child(line_text);
+    }
+}
+
+pub fn return_to_menu(actions: ActionInput, mut next_state: ResMut<NextState<ScreenState>>) {
+    if actions.just_released(Action::Back) {
+        next_state.set(ScreenState::Menu);
+    }
+}
+
+pub fn deconstruct_stats_menu(mut commands: Commands, uiroot: Single<Entity, With<UiRoot>>) {
+    info!("Deconstructing stats screen");
+    commands.entity(*uiroot).despawn_related::<Children>();
+}
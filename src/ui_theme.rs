@@ -1,25 +1,163 @@
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{ron_asset::RonAssetLoader, save, screen::focus::Focused};
+
+// Themes are RON files under assets/themes, so they can be tweaked without a rebuild.
+// With the dev feature on, saving a theme file restyles the running game.
+
+const SAVE_NAME: &str = "theme";
+const SCALE_SAVE_NAME: &str = "ui_scale";
 
 pub struct UiThemePlugin;
 
 impl Plugin for UiThemePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup);
+        app.init_asset::<ThemeFile>()
+            .init_asset_loader::<RonAssetLoader<ThemeFile>>()
+            .insert_resource(UiTheme::default())
+            .insert_resource(save::load::<ThemeSettings>(SAVE_NAME))
+            .insert_resource(save::load::<UiScaleSettings>(SCALE_SAVE_NAME))
+            .add_systems(Startup, load_themes)
+            .add_systems(
+                Update,
+                (
+                    apply_theme.run_if(
+                        resource_changed::<ThemeSettings>.or(on_event::<AssetEvent<ThemeFile>>),
+                    ),
+                    restyle_ui.run_if(resource_changed::<UiTheme>),
+                    save_theme.run_if(resource_changed::<ThemeSettings>),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (apply_ui_scale, save_ui_scale).run_if(resource_changed::<UiScaleSettings>),
+            );
+    }
+}
+
+/// The themes shipped in assets/themes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    #[default]
+    Default,
+    HighContrast,
+    LargeText,
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 3] = [
+        ThemeChoice::Default,
+        ThemeChoice::HighContrast,
+        ThemeChoice::LargeText,
+    ];
+
+    pub fn next(&self) -> Self {
+        match self {
+            ThemeChoice::Default => ThemeChoice::HighContrast,
+            ThemeChoice::HighContrast => ThemeChoice::LargeText,
+            ThemeChoice::LargeText => ThemeChoice::Default,
+        }
+    }
+
+    fn path(&self) -> &'static str {
+        match self {
+            ThemeChoice::Default => "themes/default.ron",
+            ThemeChoice::HighContrast => "themes/high_contrast.ron",
+            ThemeChoice::LargeText => "themes/large_text.ron",
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct ThemeSettings {
+    pub theme: ThemeChoice,
+}
+
+/// Size of every pixel measurement and font in the UI, in percent
+#[derive(Resource, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct UiScaleSettings {
+    percent: u32,
+}
+
+impl UiScaleSettings {
+    const MIN: u32 = 75;
+    const MAX: u32 = 200;
+    const STEP: u32 = 25;
+
+    pub fn percent(&self) -> u32 {
+        self.percent
+    }
+
+    pub fn increase(&mut self) {
+        self.percent = (self.percent + Self::STEP).min(Self::MAX);
+    }
+
+    pub fn decrease(&mut self) {
+        self.percent = self.percent.saturating_sub(Self::STEP).max(Self::MIN);
+    }
+}
+
+impl Default for UiScaleSettings {
+    fn default() -> Self {
+        UiScaleSettings { percent: 100 }
     }
 }
 
-const BUTTON_BACKGROUND_HOVER: Color = Color::srgb(1.85, 0.15, 1.0);
-const BUTTON_BACKGROUND_NORMAL: Color = Color::srgb(0.0, 0.0, 0.0);
-const BUTTON_BACKGROUND_PRESSED: Color = Color::srgb(0.95, 0.95, 0.95);
+/// A theme as written in its RON file
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct ThemeFile {
+    name: String,
+    font: String,
+    font_size: f32,
+    text_color: Color,
 
-const BUTTON_BORDER_HOVER: Color = Color::srgb(0.15, 0.15, 0.15);
-const BUTTON_BORDER_NORMAL: Color = Color::srgb(0.25, 0.25, 0.25);
-const BUTTON_BORDER_PRESSED: Color = Color::srgb(0.55, 0.55, 0.55);
+    button_background_hover: Color,
+    button_background_normal: Color,
+    button_background_pressed: Color,
 
-const TEXT_FONT_PATH: &str = "fonts/OpenDyslexic-Regular.otf";
+    button_border_hover: Color,
+    button_border_normal: Color,
+    button_border_pressed: Color,
 
+    hud_background: Color,
+    hud_text_color: Color,
+}
+
+/// Keeps every theme loaded, so switching is instant and each file is watched
 #[derive(Resource)]
+struct ThemeFiles(HashMap<ThemeChoice, Handle<ThemeFile>>);
+
+/// Which theme style a text was spawned with, so a theme change can restyle it
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextRole {
+    /// The theme font in the theme text color
+    Body,
+    /// The larger HUD counters, in the HUD text color
+    Hud,
+    /// The theme font, in a color picked by whatever spawned it
+    Font,
+}
+
+/// Which theme colors a node was spawned with, so a theme change can restyle it
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeRole {
+    Button,
+    /// The backdrop behind HUD readouts
+    Panel,
+    /// A box shaped like a button that isn't one. Highlighted cards take the hover color as their border
+    Card {
+        highlighted: bool,
+    },
+    /// The filled part of a slider
+    Fill,
+}
+
+#[derive(Resource, Clone)]
 pub struct UiTheme {
+    pub name: String,
+
     pub button_background_hover: Color,
     pub button_background_normal: Color,
     pub button_background_pressed: Color,
@@ -28,44 +166,197 @@ pub struct UiTheme {
     pub button_border_normal: Color,
     pub button_border_pressed: Color,
 
+    pub text_color: Color,
+    pub hud_background: Color,
+    pub hud_text_color: Color,
+
     text_font: TextFont,
 }
 impl UiTheme {
-    fn set_font(&mut self, font: TextFont) -> &mut Self {
-        self.text_font = font;
-        self
+    /// The theme font, for text colored some other way
+    pub fn font(&self) -> (TextFont, TextRole) {
+        (self.role_font(TextRole::Font), TextRole::Font)
+    }
+
+    /// The theme font in the theme text color
+    pub fn text_style(&self) -> (TextFont, TextColor, TextRole) {
+        (
+            self.role_font(TextRole::Body),
+            TextColor(self.text_color),
+            TextRole::Body,
+        )
+    }
+
+    /// Larger text for the HUD counters
+    pub fn hud_text_style(&self) -> (TextFont, TextColor, TextRole) {
+        (
+            self.role_font(TextRole::Hud),
+            TextColor(self.hud_text_color),
+            TextRole::Hud,
+        )
+    }
+
+    fn role_font(&self, role: TextRole) -> TextFont {
+        match role {
+            TextRole::Hud => self
+                .text_font
+                .clone()
+                .with_font_size(self.text_font.font_size * HUD_FONT_SCALE),
+            TextRole::Body | TextRole::Font => self.text_font.clone(),
+        }
+    }
+
+    fn role_text_color(&self, role: TextRole) -> Option<Color> {
+        match role {
+            TextRole::Body => Some(self.text_color),
+            TextRole::Hud => Some(self.hud_text_color),
+            TextRole::Font => None,
+        }
+    }
+
+    /// A button's background follows its interaction, the way the button systems set it
+    fn role_background(&self, role: NodeRole, interaction: Option<&Interaction>) -> Color {
+        match (role, interaction) {
+            (NodeRole::Button, Some(Interaction::Hovered)) => self.button_background_hover,
+            (NodeRole::Button, Some(Interaction::Pressed)) => self.button_background_pressed,
+            (NodeRole::Button | NodeRole::Card { .. }, _) => self.button_background_normal,
+            (NodeRole::Panel, _) => self.hud_background,
+            (NodeRole::Fill, _) => self.text_color,
+        }
+    }
+
+    fn role_border(&self, role: NodeRole) -> Color {
+        match role {
+            NodeRole::Card { highlighted: true } => self.button_background_hover,
+            _ => self.button_border_normal,
+        }
     }
 
-    pub fn font(&self) -> TextFont {
-        self.text_font.clone()
+    fn from_file(file: &ThemeFile, asset_server: &AssetServer) -> Self {
+        UiTheme {
+            name: file.name.clone(),
+            button_background_hover: file.button_background_hover,
+            button_background_normal: file.button_background_normal,
+            button_background_pressed: file.button_background_pressed,
+            button_border_hover: file.button_border_hover,
+            button_border_normal: file.button_border_normal,
+            button_border_pressed: file.button_border_pressed,
+            text_color: file.text_color,
+            hud_background: file.hud_background,
+            hud_text_color: file.hud_text_color,
+            text_font: TextFont {
+                font: asset_server.load(&file.font),
+                font_size: file.font_size,
+                font_smoothing: bevy::text::FontSmoothing::AntiAliased,
+                ..default()
+            },
+        }
     }
 }
+
+const HUD_FONT_SCALE: f32 = 2.0;
+
+/// Used until the theme file has loaded
 impl Default for UiTheme {
     fn default() -> Self {
         UiTheme {
-            button_background_hover: BUTTON_BACKGROUND_HOVER,
-            button_background_normal: BUTTON_BACKGROUND_NORMAL,
-            button_background_pressed: BUTTON_BACKGROUND_PRESSED,
-            button_border_hover: BUTTON_BORDER_HOVER,
-            button_border_normal: BUTTON_BORDER_NORMAL,
-            button_border_pressed: BUTTON_BORDER_PRESSED,
-            text_font: TextFont { ..default() },
+            name: String::from("Default"),
+            button_background_hover: Color::srgb(1.85, 0.15, 1.0),
+            button_background_normal: Color::srgb(0.0, 0.0, 0.0),
+            button_background_pressed: Color::srgb(0.95, 0.95, 0.95),
+            button_border_hover: Color::srgb(0.15, 0.15, 0.15),
+            button_border_normal: Color::srgb(0.25, 0.25, 0.25),
+            button_border_pressed: Color::srgb(0.55, 0.55, 0.55),
+            text_color: Color::WHITE,
+            hud_background: Color::srgba(0.0, 0.0, 0.0, 0.8),
+            hud_text_color: Color::WHITE,
+            text_font: TextFont {
+                font_size: 12.0,
+                ..default()
+            },
         }
     }
 }
 
-fn setup(mut commands: Commands, asset_server: ResMut<AssetServer>) {
-    let font_handle: Handle<Font> = asset_server.load(TEXT_FONT_PATH);
-    let text_font = TextFont {
-        font: font_handle,
-        font_size: 12.0,
-        font_smoothing: bevy::text::FontSmoothing::AntiAliased,
+//Systems
 
-        ..default()
+fn load_themes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let files = ThemeChoice::ALL
+        .into_iter()
+        .map(|choice| (choice, asset_server.load(choice.path())))
+        .collect();
+    commands.insert_resource(ThemeFiles(files));
+}
+
+///Rebuilds the theme when another is picked, or when its file finishes loading or is edited
+fn apply_theme(
+    settings: Res<ThemeSettings>,
+    theme_files: Res<ThemeFiles>,
+    files: Res<Assets<ThemeFile>>,
+    asset_server: Res<AssetServer>,
+    mut theme: ResMut<UiTheme>,
+) {
+    let Some(file) = theme_files
+        .0
+        .get(&settings.theme)
+        .and_then(|handle| files.get(handle))
+    else {
+        return;
     };
+    *theme = UiTheme::from_file(file, &asset_server);
+}
+
+///Carries a theme change over to the UI already on screen, restyling each themed text and node by its role.
+///A focused button keeps its highlight, and takes the new border once focus moves on
+fn restyle_ui(
+    theme: Res<UiTheme>,
+    mut text_q: Query<(&mut TextFont, Option<&mut TextColor>, &TextRole)>,
+    mut node_q: Query<(
+        &NodeRole,
+        &mut BackgroundColor,
+        Option<&mut BorderColor>,
+        Option<&Interaction>,
+        Option<&mut Focused>,
+    )>,
+) {
+    for (mut font, color, role) in text_q.iter_mut() {
+        *font = theme.role_font(*role);
+        if let (Some(mut color), Some(text_color)) = (color, theme.role_text_color(*role)) {
+            color.0 = text_color;
+        }
+    }
 
-    let mut theme = UiTheme::default();
-    theme.set_font(text_font);
+    for (role, mut background, border, interaction, focused) in node_q.iter_mut() {
+        background.0 = theme.role_background(*role, interaction);
+        if *role == NodeRole::Panel || *role == NodeRole::Fill {
+            continue;
+        }
+        let Some(mut border) = border else {
+            continue;
+        };
+        match focused {
+            Some(mut focused) => {
+                focused.0 = BorderColor(theme.role_border(*role));
+                border.0 = theme.button_background_hover;
+            }
+            None => border.0 = theme.role_border(*role),
+        }
+    }
+}
+
+fn save_theme(settings: Res<ThemeSettings>) {
+    save::save(SAVE_NAME, &*settings);
+}
+
+///UiScale grows every Px size and font, which covers the buttons and HUD.
+///Hand edited saves are clamped back into range
+fn apply_ui_scale(settings: Res<UiScaleSettings>, mut ui_scale: ResMut<UiScale>) {
+    let percent = settings
+        .percent
+        .clamp(UiScaleSettings::MIN, UiScaleSettings::MAX);
+    ui_scale.0 = percent as f32 / 100.0;
+}
 
-    commands.insert_resource(theme);
+fn save_ui_scale(settings: Res<UiScaleSettings>) {
+    save::save(SCALE_SAVE_NAME, &*settings);
 }